@@ -0,0 +1,109 @@
+// Copyright (c) 2026, Awiteb <a@4rs.nl>
+//     lightweight nostr Web of Trust library
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use std::{collections::HashSet, hint::black_box};
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use nostr_wot::{WotGraph, relations::Relation};
+use petgraph::{Direction, graph::NodeIndex};
+
+/// Number of nodes of the synthetic graph, each following 8 pseudo-random
+/// nodes.
+const NODES: u64 = 1_000_000;
+/// Number of queries per iteration.
+const QUERIES: u64 = 1_000;
+
+/// A xorshift64 generator of node indices.
+fn random_nodes() -> impl FnMut() -> NodeIndex {
+    let mut state = 0x9E37_79B9_7F4A_7C15u64;
+    move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        NodeIndex::new((state % NODES) as usize)
+    }
+}
+
+/// The previous `HashSet` based traversal, the baseline of the bitset one.
+fn count_neighbors_in_hops_hashset(
+    graph: &WotGraph,
+    source: NodeIndex,
+    target: NodeIndex,
+    relation: Relation,
+    max_hops: u8,
+) -> usize {
+    let target_incoming: HashSet<NodeIndex> = graph
+        .neighbors(target, relation, Direction::Incoming)
+        .collect();
+    let mut visited = HashSet::new();
+    let mut current_level = vec![source];
+    let mut count = 0;
+    for hop in 0..=max_hops {
+        for node in &current_level {
+            if visited.insert(*node) && target_incoming.contains(node) {
+                count += 1;
+            }
+        }
+        if hop == max_hops {
+            break;
+        }
+        current_level = current_level
+            .iter()
+            .flat_map(|idx| graph.neighbors(*idx, Relation::Follow, Direction::Outgoing))
+            .filter(|idx| !visited.contains(idx))
+            .collect();
+    }
+    count
+}
+
+fn count_neighbors_in_hops(c: &mut Criterion) {
+    let mut random_node = random_nodes();
+    let mut graph = WotGraph::with_capacity(NODES as usize, NODES as usize * 8);
+    for i in 0..NODES {
+        graph.add_node(i).unwrap();
+    }
+    for i in 0..NODES as usize {
+        for _ in 0..8 {
+            let target = random_node();
+            graph.add_edge(NodeIndex::new(i), target, Relation::Follow);
+        }
+    }
+    let queries: Vec<_> = (0..QUERIES)
+        .map(|_| (random_node(), random_node()))
+        .collect();
+
+    let mut group = c.benchmark_group("traversal");
+    group.sample_size(10);
+    group.bench_function("count_neighbors_in_hops_4", |b| {
+        b.iter(|| {
+            for (source, target) in &queries {
+                black_box(graph.count_neighbors_in_hops(*source, *target, Relation::Follow, 4));
+            }
+        })
+    });
+    group.bench_function("count_neighbors_in_hops_4_hashset", |b| {
+        b.iter(|| {
+            for (source, target) in &queries {
+                black_box(count_neighbors_in_hops_hashset(
+                    &graph,
+                    *source,
+                    *target,
+                    Relation::Follow,
+                    4,
+                ));
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, count_neighbors_in_hops);
+criterion_main!(benches);
@@ -0,0 +1,112 @@
+// Copyright (c) 2026, Awiteb <a@4rs.nl>
+//     lightweight nostr Web of Trust library
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use std::{io::Write, sync::Arc};
+
+use arrow_array::{
+    ArrayRef,
+    Float64Array,
+    Int64Array,
+    RecordBatch,
+    StringArray,
+    UInt32Array,
+    UInt64Array,
+};
+use arrow_schema::{DataType, Field, Schema};
+use nostr::key::PublicKey;
+use parquet::arrow::ArrowWriter;
+
+use crate::{
+    WotGraph,
+    error::Error,
+    formats::Scores,
+    options::AttributeOptions,
+    relations::Relation,
+    utils,
+};
+
+/// The nodes table, `index`, `hash`, `pubkey` and the score columns.
+pub fn nodes_batch(
+    graph: &WotGraph,
+    known: &[PublicKey],
+    options: &AttributeOptions,
+) -> Result<RecordBatch, Error> {
+    let known = utils::known_keys(known);
+    let nodes = graph.inner.raw_nodes();
+    let scores = Scores::new(graph, options)?;
+
+    let mut fields = vec![
+        Field::new("index", DataType::UInt32, false),
+        Field::new("hash", DataType::UInt64, false),
+        Field::new("pubkey", DataType::Utf8, true),
+    ];
+    let mut columns: Vec<ArrayRef> = vec![
+        Arc::new(UInt32Array::from_iter_values(0..nodes.len() as u32)),
+        Arc::new(UInt64Array::from_iter_values(
+            nodes.iter().map(|node| node.weight),
+        )),
+        Arc::new(StringArray::from_iter(
+            nodes
+                .iter()
+                .map(|node| known.get(&node.weight).map(|pkey| pkey.to_hex())),
+        )),
+    ];
+    if let Some(ranks) = scores.page_rank {
+        fields.push(Field::new("page_rank", DataType::Float64, false));
+        columns.push(Arc::new(Float64Array::from(ranks)));
+    }
+    if let Some(scores) = scores.dump_wot {
+        fields.push(Field::new("dump_wot", DataType::Int64, false));
+        columns.push(Arc::new(Int64Array::from_iter_values(
+            scores.into_iter().map(|score| score as i64),
+        )));
+    }
+
+    Ok(RecordBatch::try_new(
+        Arc::new(Schema::new(fields)),
+        columns,
+    )?)
+}
+
+/// The edges table, `source` and `target` node indices and `relation`.
+pub fn edges_batch(graph: &WotGraph) -> Result<RecordBatch, Error> {
+    let edges = graph.inner.raw_edges();
+    let relations = edges
+        .iter()
+        .map(|edge| Ok(Some(Relation::try_from(edge.weight)?.as_str())))
+        .collect::<Result<StringArray, Error>>()?;
+
+    let schema = Schema::new(vec![
+        Field::new("source", DataType::UInt32, false),
+        Field::new("target", DataType::UInt32, false),
+        Field::new("relation", DataType::Utf8, false),
+    ]);
+    Ok(RecordBatch::try_new(
+        Arc::new(schema),
+        vec![
+            Arc::new(UInt32Array::from_iter_values(
+                edges.iter().map(|edge| edge.source().index() as u32),
+            )),
+            Arc::new(UInt32Array::from_iter_values(
+                edges.iter().map(|edge| edge.target().index() as u32),
+            )),
+            Arc::new(relations),
+        ],
+    )?)
+}
+
+/// Writes the record batch as a Parquet file.
+pub fn write_parquet<W: Write + Send>(writer: W, batch: &RecordBatch) -> Result<(), Error> {
+    let mut writer = ArrowWriter::try_new(writer, batch.schema(), None)?;
+    writer.write(batch)?;
+    writer.close()?;
+    Ok(())
+}
@@ -0,0 +1,85 @@
+// Copyright (c) 2026, Awiteb <a@4rs.nl>
+//     lightweight nostr Web of Trust library
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use async_compression::{
+    Level,
+    tokio::{bufread::GzipDecoder, write::GzipEncoder},
+};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+
+use crate::{
+    options::{Codec, ExportOptions, ImportOptions},
+    parser::{CHUNK_SIZE, GZIP_MAGIC, GraphDecoder, GraphEncoder, is_v0_header},
+};
+
+/// Export the graph, same as the sync `parser::export_graph`.
+pub async fn export_graph<W: AsyncWrite + Unpin>(
+    graph: &crate::GraphType,
+    writer: &mut W,
+    options: &ExportOptions,
+) -> Result<(), crate::error::Error> {
+    let mut encoder = GraphEncoder::new(graph, options)?;
+    let mut chunk = Vec::new();
+    while encoder.next_chunk(&mut chunk)? {
+        writer.write_all(&chunk).await?;
+    }
+    writer.flush().await?;
+    Ok(())
+}
+
+/// Export the graph compressed with gzip and the options level, see
+/// [`export_graph`]. The writer is shut down to finish the gzip stream.
+pub async fn export_gzip<W: AsyncWrite + Unpin>(
+    graph: &crate::GraphType,
+    writer: &mut W,
+    options: &ExportOptions,
+) -> Result<(), crate::error::Error> {
+    let level = Codec::Gzip.level(options.level)?;
+    let mut encoder = GzipEncoder::with_quality(writer, Level::Precise(level as i32));
+    export_graph(graph, &mut encoder, options).await?;
+    encoder.shutdown().await?;
+    Ok(())
+}
+
+/// Import the graph, raw or gzip-compressed. Same as the sync
+/// `parser::import_graph_auto`, but the other codecs are not supported.
+pub async fn import_graph_auto<R: AsyncRead + Unpin>(
+    data: R,
+    options: &ImportOptions,
+) -> Result<crate::GraphType, crate::error::Error> {
+    let mut data = BufReader::new(data);
+    let magic = data.fill_buf().await?;
+    if !is_v0_header(magic) && magic.starts_with(&GZIP_MAGIC) {
+        return import_graph(GzipDecoder::new(data), options).await;
+    }
+    import_graph(data, options).await
+}
+
+/// Import the graph, same as the sync `parser::import_graph`.
+async fn import_graph<R: AsyncRead + Unpin>(
+    mut data: R,
+    options: &ImportOptions,
+) -> Result<crate::GraphType, crate::error::Error> {
+    let mut decoder = GraphDecoder::new(options);
+    let mut buffer = vec![0; CHUNK_SIZE];
+    let mut len = 0;
+    while !decoder.is_done() {
+        let read = data.read(&mut buffer[len..]).await?;
+        if read == 0 {
+            break;
+        }
+        len += read;
+        let decoded = decoder.decode(&buffer[..len])?;
+        buffer.copy_within(decoded..len, 0);
+        len -= decoded;
+    }
+    decoder.finish()
+}
@@ -0,0 +1,81 @@
+// Copyright (c) 2026, Awiteb <a@4rs.nl>
+//     lightweight nostr Web of Trust library
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use std::io::{Read, Write};
+
+use nostr::key::PublicKey;
+
+use crate::{
+    error::Error,
+    options::ImportOptions,
+    parser::GraphBuilder,
+    relations::Relation,
+    utils,
+};
+
+/// The header of the CSV edge list.
+const HEADER: [&str; 3] = ["follower", "followee", "relation"];
+
+/// Imports a graph from a CSV edge list using the given options, one record
+/// at a time. The nodes are added as they appear in the edges.
+pub fn import_graph<R: Read>(
+    reader: R,
+    options: &ImportOptions,
+) -> Result<crate::GraphType, Error> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .trim(csv::Trim::All)
+        .from_reader(reader);
+    let mut graph = GraphBuilder::with_counts(0, 0, options)?;
+
+    let mut record = csv::StringRecord::new();
+    while reader.read_record(&mut record)? {
+        if record.position().is_some_and(|pos| pos.record() == 0) && record == HEADER[..] {
+            continue;
+        }
+        let [follower, followee, relation] =
+            [0, 1, 2].map(|idx| record.get(idx).unwrap_or_default());
+
+        let relation: Relation = relation.parse()?;
+        let source = graph.node(utils::parse_node_key(follower)?)?;
+        let target = graph.node(utils::parse_node_key(followee)?)?;
+        graph.push_edge(source, relation as u8, target)?;
+    }
+
+    graph.finish()
+}
+
+/// Exports the graph edges as CSV, the nodes of the `known` public keys are
+/// written as hex public keys.
+pub fn export_graph<W: Write>(
+    graph: &crate::GraphType,
+    writer: W,
+    known: &[PublicKey],
+) -> Result<(), Error> {
+    let known = utils::known_keys(known);
+    let keys: Vec<String> = graph
+        .raw_nodes()
+        .iter()
+        .map(|node| utils::node_key(&known, node.weight))
+        .collect();
+
+    let mut writer = csv::Writer::from_writer(writer);
+    writer.write_record(HEADER)?;
+    for edge in graph.raw_edges() {
+        writer.write_record([
+            keys[edge.source().index()].as_str(),
+            keys[edge.target().index()].as_str(),
+            Relation::try_from(edge.weight)?.as_str(),
+        ])?;
+    }
+    writer.flush()?;
+    Ok(())
+}
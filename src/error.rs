@@ -9,17 +9,51 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
 // THE SOFTWARE.
 
-use std::collections::TryReserveError;
+use std::{collections::TryReserveError, fmt};
+
+/// The limit exceeded by an imported graph
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    /// The maximum number of nodes
+    Nodes,
+    /// The maximum number of edges
+    Edges,
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Nodes => f.write_str("nodes"),
+            Self::Edges => f.write_str("edges"),
+        }
+    }
+}
 
 /// Graph serialization error
 #[derive(Debug, thiserror::Error)]
 pub enum GraphSerializationError {
     #[error("Invalid data: insufficient bytes (expected at least {0}, got less)")]
     InsufficientData(usize),
-    #[error("Invalid format: missing separator between nodes and edges")]
+    #[error("Invalid format: malformed graph data")]
     InvalidFormat,
     #[error("Node not found in graph: {0}")]
     NodeNotFound(u64),
+    #[error("Invalid relation: {0}")]
+    InvalidRelation(u8),
+    #[error("Invalid format: not a WoT graph")]
+    NotAWotGraph,
+    #[error("Unsupported format version: {0}")]
+    UnsupportedVersion(u32),
+    #[error("Checksum mismatch: stored {0:#018x}, computed {1:#018x}")]
+    ChecksumMismatch(u64, u64),
+    #[error("Limit exceeded: {1} {0}, the maximum is {2}")]
+    LimitExceeded(Limit, u64, usize),
+    #[error("Duplicate node in graph: {0}")]
+    DuplicateNode(u64),
+    #[error("Unknown relation name: {0}")]
+    UnknownRelation(String),
+    #[error("Invalid node key, expected a hex or npub public key or a 16 hex hash: {0}")]
+    InvalidNodeKey(String),
 }
 
 
@@ -32,4 +66,24 @@ pub enum Error {
     GraphSerializationError(#[from] GraphSerializationError),
     #[error("Failed to allocate memory: {0}")]
     MemoryAllocation(#[from] TryReserveError),
+    #[error("Operation cancelled")]
+    Cancelled,
+    #[error("Invalid damping factor, expected a value between 0 and 1: {0}")]
+    InvalidDampingFactor(f64),
+    #[error("Graph error: {0}")]
+    Graph(#[from] petgraph::graph::GraphError),
+    #[error("Unsupported compression level of {0:?}: {1}")]
+    InvalidCompressionLevel(crate::options::Codec, u32),
+    #[cfg(feature = "arrow")]
+    #[error("Arrow error: {0}")]
+    Arrow(#[from] arrow_schema::ArrowError),
+    #[cfg(feature = "arrow")]
+    #[error("Parquet error: {0}")]
+    Parquet(#[from] parquet::errors::ParquetError),
+    #[cfg(feature = "csv")]
+    #[error("CSV error: {0}")]
+    Csv(#[from] csv::Error),
+    #[cfg(feature = "json")]
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
 }
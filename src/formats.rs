@@ -0,0 +1,295 @@
+// Copyright (c) 2026, Awiteb <a@4rs.nl>
+//     lightweight nostr Web of Trust library
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use std::io::{BufWriter, Write};
+
+use petgraph::{Direction, graph::NodeIndex, visit::EdgeRef};
+
+use crate::{
+    WotGraph,
+    error::Error,
+    options::AttributeOptions,
+    relations::Relation,
+    traits::{basic::BasicOperationsExt, page_rank::PageRankExt},
+};
+
+/// The node score attributes, indexed by node index.
+pub(crate) struct Scores {
+    pub(crate) page_rank: Option<Vec<f64>>,
+    pub(crate) dump_wot:  Option<Vec<isize>>,
+}
+
+impl Scores {
+    /// Computes the scores of the options. Returns
+    /// [`Error::InvalidDampingFactor`] if the PageRank damping factor is not
+    /// between 0 and 1.
+    pub(crate) fn new(graph: &WotGraph, options: &AttributeOptions) -> Result<Self, Error> {
+        let page_rank = match options.page_rank {
+            Some((damping_factor, _)) if !(0.0..=1.0).contains(&damping_factor) => {
+                return Err(Error::InvalidDampingFactor(damping_factor));
+            }
+            Some((damping_factor, iterations)) => {
+                Some(graph.inner.page_rank(damping_factor, iterations))
+            }
+            None => None,
+        };
+
+        Ok(Self {
+            page_rank,
+            dump_wot: options.dump_wot.map(|(source, max_hops)| {
+                let mut view = graph.source_view(source, max_hops);
+                graph
+                    .inner
+                    .node_indices()
+                    .map(|node| view.dump_wot(graph, node))
+                    .collect()
+            }),
+        })
+    }
+
+    /// The `(name, value)` scores of a node.
+    fn of(&self, node: usize) -> impl Iterator<Item = (&'static str, String)> {
+        let page_rank = self
+            .page_rank
+            .as_ref()
+            .map(|ranks| ("page_rank", ranks[node].to_string()));
+        let dump_wot = self
+            .dump_wot
+            .as_ref()
+            .map(|scores| ("dump_wot", scores[node].to_string()));
+        page_rank.into_iter().chain(dump_wot)
+    }
+
+    /// The `(name, type)` of the computed scores.
+    fn attributes(&self) -> impl Iterator<Item = (&'static str, &'static str)> {
+        let page_rank = self.page_rank.as_ref().map(|_| ("page_rank", "double"));
+        let dump_wot = self.dump_wot.as_ref().map(|_| ("dump_wot", "long"));
+        page_rank.into_iter().chain(dump_wot)
+    }
+}
+
+/// The edges of the graph as `(source, target, relation)`.
+fn edges(
+    graph: &WotGraph,
+) -> impl Iterator<Item = Result<(NodeIndex, NodeIndex, Relation), Error>> {
+    graph.inner.raw_edges().iter().map(|edge| {
+        Ok((
+            edge.source(),
+            edge.target(),
+            Relation::try_from(edge.weight)?,
+        ))
+    })
+}
+
+/// Exports the graph as GraphML.
+pub fn export_graphml<W: Write>(
+    graph: &WotGraph,
+    writer: W,
+    options: &AttributeOptions,
+) -> Result<(), Error> {
+    let scores = Scores::new(graph, options)?;
+    let mut writer = BufWriter::new(writer);
+
+    writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        writer,
+        r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#
+    )?;
+    writeln!(
+        writer,
+        r#"  <key id="hash" for="node" attr.name="hash" attr.type="string"/>"#
+    )?;
+    for (name, ty) in scores.attributes() {
+        writeln!(
+            writer,
+            r#"  <key id="{name}" for="node" attr.name="{name}" attr.type="{ty}"/>"#
+        )?;
+    }
+    writeln!(
+        writer,
+        r#"  <key id="relation" for="edge" attr.name="relation" attr.type="string"/>"#
+    )?;
+    writeln!(writer, r#"  <graph id="wot" edgedefault="directed">"#)?;
+
+    for (idx, node) in graph.inner.raw_nodes().iter().enumerate() {
+        writeln!(writer, r#"    <node id="n{idx}">"#)?;
+        writeln!(
+            writer,
+            r#"      <data key="hash">{:016x}</data>"#,
+            node.weight
+        )?;
+        for (name, value) in scores.of(idx) {
+            writeln!(writer, r#"      <data key="{name}">{value}</data>"#)?;
+        }
+        writeln!(writer, "    </node>")?;
+    }
+    for (idx, edge) in edges(graph).enumerate() {
+        let (source, target, relation) = edge?;
+        writeln!(
+            writer,
+            r#"    <edge id="e{idx}" source="n{}" target="n{}">"#,
+            source.index(),
+            target.index()
+        )?;
+        writeln!(writer, r#"      <data key="relation">{relation}</data>"#)?;
+        writeln!(writer, "    </edge>")?;
+    }
+
+    writeln!(writer, "  </graph>")?;
+    writeln!(writer, "</graphml>")?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Exports the graph as GEXF 1.3.
+pub fn export_gexf<W: Write>(
+    graph: &WotGraph,
+    writer: W,
+    options: &AttributeOptions,
+) -> Result<(), Error> {
+    let scores = Scores::new(graph, options)?;
+    let mut writer = BufWriter::new(writer);
+
+    writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        writer,
+        r#"<gexf xmlns="http://gexf.net/1.3" version="1.3">"#
+    )?;
+    writeln!(writer, r#"  <graph defaultedgetype="directed">"#)?;
+    writeln!(writer, r#"    <attributes class="node">"#)?;
+    writeln!(
+        writer,
+        r#"      <attribute id="hash" title="hash" type="string"/>"#
+    )?;
+    for (name, ty) in scores.attributes() {
+        writeln!(
+            writer,
+            r#"      <attribute id="{name}" title="{name}" type="{ty}"/>"#
+        )?;
+    }
+    writeln!(writer, "    </attributes>")?;
+    writeln!(writer, r#"    <attributes class="edge">"#)?;
+    writeln!(
+        writer,
+        r#"      <attribute id="relation" title="relation" type="string"/>"#
+    )?;
+    writeln!(writer, "    </attributes>")?;
+
+    writeln!(writer, "    <nodes>")?;
+    for (idx, node) in graph.inner.raw_nodes().iter().enumerate() {
+        writeln!(
+            writer,
+            r#"      <node id="{idx}" label="{:016x}">"#,
+            node.weight
+        )?;
+        writeln!(writer, "        <attvalues>")?;
+        writeln!(
+            writer,
+            r#"          <attvalue for="hash" value="{:016x}"/>"#,
+            node.weight
+        )?;
+        for (name, value) in scores.of(idx) {
+            writeln!(
+                writer,
+                r#"          <attvalue for="{name}" value="{value}"/>"#
+            )?;
+        }
+        writeln!(writer, "        </attvalues>")?;
+        writeln!(writer, "      </node>")?;
+    }
+    writeln!(writer, "    </nodes>")?;
+
+    writeln!(writer, "    <edges>")?;
+    for (idx, edge) in edges(graph).enumerate() {
+        let (source, target, relation) = edge?;
+        writeln!(
+            writer,
+            r#"      <edge id="{idx}" source="{}" target="{}" label="{relation}">"#,
+            source.index(),
+            target.index()
+        )?;
+        writeln!(writer, "        <attvalues>")?;
+        writeln!(
+            writer,
+            r#"          <attvalue for="relation" value="{relation}"/>"#
+        )?;
+        writeln!(writer, "        </attvalues>")?;
+        writeln!(writer, "      </edge>")?;
+    }
+    writeln!(writer, "    </edges>")?;
+
+    writeln!(writer, "  </graph>")?;
+    writeln!(writer, "</gexf>")?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Exports the network of `source` within `max_hops` as Graphviz DOT.
+pub fn export_dot<W: Write>(
+    graph: &WotGraph,
+    writer: W,
+    source: NodeIndex,
+    max_hops: u8,
+    target: Option<NodeIndex>,
+) -> Result<(), Error> {
+    let hops = graph.inner.nodes_in_hops(source, max_hops);
+    let mut nodes: Vec<NodeIndex> = hops.keys().copied().collect();
+    nodes.extend(
+        target.filter(|target| {
+            target.index() < graph.inner.node_count() && !hops.contains_key(target)
+        }),
+    );
+    nodes.sort_unstable();
+    let mut writer = BufWriter::new(writer);
+
+    writeln!(writer, "digraph wot {{")?;
+    writeln!(writer, r#"  node [shape=box, fontname="monospace"];"#)?;
+    for node in &nodes {
+        write!(
+            writer,
+            r#"  n{} [label="{:016x}"#,
+            node.index(),
+            graph.inner[*node]
+        )?;
+        if let Some(hop) = hops.get(node) {
+            write!(writer, r"\nhop {hop}")?;
+        }
+        write!(writer, "\"")?;
+        if *node == source {
+            write!(writer, r#", style=filled, fillcolor="lightblue""#)?;
+        } else if Some(*node) == target {
+            write!(writer, r#", style=filled, fillcolor="gold""#)?;
+        }
+        writeln!(writer, "];")?;
+    }
+
+    for node in &nodes {
+        for edge in graph.inner.edges_directed(*node, Direction::Outgoing) {
+            if nodes.binary_search(&edge.target()).is_err() {
+                continue;
+            }
+            let style = match Relation::try_from(*edge.weight())? {
+                Relation::Follow => r#"color="darkgreen", label="follow""#,
+                Relation::Mute => r#"color="red", style=dashed, label="mute""#,
+            };
+            writeln!(
+                writer,
+                "  n{} -> n{} [{style}];",
+                node.index(),
+                edge.target().index()
+            )?;
+        }
+    }
+
+    writeln!(writer, "}}")?;
+    writer.flush()?;
+    Ok(())
+}
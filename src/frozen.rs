@@ -0,0 +1,282 @@
+// Copyright (c) 2026, Awiteb <a@4rs.nl>
+//     lightweight nostr Web of Trust library
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use std::io::Write;
+#[cfg(not(any(target_arch = "wasm32", target_arch = "wasm64")))]
+use std::{fs::File, io::BufWriter, path::Path};
+
+use byteorder::{LittleEndian, WriteBytesExt};
+use nostr::key::PublicKey;
+use petgraph::{Direction, graph::NodeIndex};
+
+use crate::{
+    WotGraph,
+    mapped,
+    relations::Relation,
+    traits::{
+        basic::{self, Adjacency},
+        dump_wot,
+    },
+    utils,
+};
+
+/// The relations stored in a [`FrozenWotGraph`].
+const RELATIONS: [Relation; 2] = [Relation::Follow, Relation::Mute];
+/// The directions stored in a [`FrozenWotGraph`].
+const DIRECTIONS: [Direction; 2] = [Direction::Outgoing, Direction::Incoming];
+
+/// Compressed sparse row adjacency of a relation in a direction.
+#[derive(Debug, Clone)]
+struct Csr {
+    /// `offsets[n]..offsets[n + 1]` is the range of the node `n` neighbors.
+    offsets:   Vec<u32>,
+    /// The neighbors of all the nodes, sorted by index for each node.
+    neighbors: Vec<u32>,
+}
+
+impl Csr {
+    /// Builds the adjacency of the `relation` edges in the `direction`.
+    fn new(graph: &crate::GraphType, relation: Relation, direction: Direction) -> Self {
+        let edges = graph
+            .raw_edges()
+            .iter()
+            .filter(|edge| edge.weight == relation as u8)
+            .map(|edge| {
+                match direction {
+                    Direction::Outgoing => (edge.source().index(), edge.target().index() as u32),
+                    Direction::Incoming => (edge.target().index(), edge.source().index() as u32),
+                }
+            });
+
+        let mut offsets = vec![0u32; graph.node_count() + 1];
+        for (node, _) in edges.clone() {
+            offsets[node + 1] += 1;
+        }
+        for idx in 1..offsets.len() {
+            offsets[idx] += offsets[idx - 1];
+        }
+
+        let mut cursors = offsets.clone();
+        let mut neighbors = vec![0u32; offsets[offsets.len() - 1] as usize];
+        for (node, neighbor) in edges {
+            neighbors[cursors[node] as usize] = neighbor;
+            cursors[node] += 1;
+        }
+        for node in offsets.windows(2) {
+            neighbors[node[0] as usize..node[1] as usize].sort_unstable();
+        }
+
+        Self { offsets, neighbors }
+    }
+
+    /// The neighbors of the node, empty if it doesn't exist.
+    #[inline]
+    fn neighbors(&self, node: NodeIndex) -> &[u32] {
+        match (
+            self.offsets.get(node.index()),
+            self.offsets.get(node.index() + 1),
+        ) {
+            (Some(start), Some(end)) => &self.neighbors[*start as usize..*end as usize],
+            _ => &[],
+        }
+    }
+}
+
+/// An immutable Web of Trust graph, stored in a compressed sparse row (CSR)
+/// layout for read-heavy workloads.
+///
+/// Each relation and direction has its own offsets and neighbors arrays, so
+/// the neighbors of a node are contiguous in memory instead of the linked
+/// lists of [`WotGraph`]. The node indices are the same as the graph it's
+/// built from. Edges with an unknown relation are not kept.
+///
+/// ```rust
+/// use nostr_wot::{WotGraph, relations::Relation};
+///
+/// let mut graph = WotGraph::new();
+/// let node1 = graph.add_node(1).unwrap();
+/// let node2 = graph.add_node(2).unwrap();
+/// let node3 = graph.add_node(3).unwrap();
+/// graph.add_edge(node1, node2, Relation::Follow).unwrap();
+/// graph.add_edge(node2, node3, Relation::Follow).unwrap();
+///
+/// let frozen = graph.freeze();
+/// assert_eq!(frozen.dump_wot(node1, node3, 2), 1);
+/// ```
+#[derive(Debug, Clone)]
+pub struct FrozenWotGraph {
+    /// The node weights (public key hashes).
+    nodes:     Vec<u64>,
+    /// The adjacency of each relation and direction, indexed by
+    /// `[relation][direction]`.
+    adjacency: [[Csr; 2]; 2],
+}
+
+impl FrozenWotGraph {
+    /// Builds a frozen graph from the given graph.
+    pub fn new(graph: &WotGraph) -> Self {
+        let inner = &graph.inner;
+        Self {
+            nodes:     inner.raw_nodes().iter().map(|node| node.weight).collect(),
+            adjacency: RELATIONS
+                .map(|relation| DIRECTIONS.map(|direction| Csr::new(inner, relation, direction))),
+        }
+    }
+
+    /// Number of nodes in the graph.
+    #[inline]
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Number of edges in the graph.
+    #[inline]
+    pub fn edge_count(&self) -> usize {
+        self.adjacency
+            .iter()
+            .map(|directions| directions[0].neighbors.len())
+            .sum()
+    }
+
+    /// The node weights (public key hashes), indexed by [`NodeIndex::index`].
+    #[inline]
+    pub fn nodes(&self) -> &[u64] {
+        &self.nodes
+    }
+
+    /// Returns the node index by public key. Returns `None` if the there is no
+    /// match
+    pub fn node_index(&self, pkey: &PublicKey) -> Option<NodeIndex> {
+        let pkey_hash = utils::hash_bytes(pkey.as_bytes());
+        self.nodes
+            .iter()
+            .position(|node| *node == pkey_hash)
+            .map(NodeIndex::new)
+    }
+
+    /// Finds the neighboring nodes of `source` based on the given `relation`
+    /// and `direction`. Same as [`WotGraph::neighbors`], sorted by index.
+    #[inline]
+    pub fn neighbors(
+        &self,
+        source: NodeIndex,
+        relation: Relation,
+        direction: Direction,
+    ) -> impl Iterator<Item = NodeIndex> {
+        self.adjacency[relation as usize][direction.index()]
+            .neighbors(source)
+            .iter()
+            .map(|idx| NodeIndex::new(*idx as usize))
+    }
+
+    /// Exports the graph in a layout that can be queried without
+    /// deserialization, see [`mapped::MappedWotGraph`] for the format.
+    pub fn export_mapped<W: Write>(&self, writer: &mut W) -> Result<(), crate::error::Error> {
+        // Writes zeros up to the next section alignment
+        fn pad<W: Write>(writer: &mut W, pos: usize) -> Result<usize, crate::error::Error> {
+            let aligned = pos.next_multiple_of(mapped::ALIGNMENT);
+            writer.write_all(&[0; mapped::ALIGNMENT][..aligned - pos])?;
+            Ok(aligned)
+        }
+
+        // Write header
+        writer.write_all(&mapped::MAGIC)?;
+        writer.write_u32::<LittleEndian>(mapped::VERSION)?;
+        writer.write_u32::<LittleEndian>(0)?;
+        writer.write_u64::<LittleEndian>(self.nodes.len() as u64)?;
+        for csr in self.adjacency.iter().flatten() {
+            writer.write_u64::<LittleEndian>(csr.neighbors.len() as u64)?;
+        }
+        writer.write_u64::<LittleEndian>(0)?;
+
+        // Write nodes
+        for node in &self.nodes {
+            writer.write_u64::<LittleEndian>(*node)?;
+        }
+
+        // Write adjacency
+        let mut pos = mapped::HEADER_SIZE + self.nodes.len() * 8;
+        for csr in self.adjacency.iter().flatten() {
+            for offset in &csr.offsets {
+                writer.write_u32::<LittleEndian>(*offset)?;
+            }
+            pos = pad(writer, pos + csr.offsets.len() * 4)?;
+            for neighbor in &csr.neighbors {
+                writer.write_u32::<LittleEndian>(*neighbor)?;
+            }
+            pos = pad(writer, pos + csr.neighbors.len() * 4)?;
+        }
+
+        Ok(())
+    }
+
+    /// Exports the graph to a file that can be memory mapped, see
+    /// [`FrozenWotGraph::export_mapped`].
+    #[cfg(not(any(target_arch = "wasm32", target_arch = "wasm64")))]
+    pub fn export_to_file_mapped<P: AsRef<Path>>(
+        &self,
+        path: P,
+    ) -> Result<(), crate::error::Error> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.export_mapped(&mut writer)?;
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    /// Counts how many nodes in the source's following hops (up to `max_hops`)
+    /// have the given `relation` with the target. Same as
+    /// [`WotGraph::count_neighbors_in_hops`].
+    #[inline]
+    pub fn count_neighbors_in_hops(
+        &self,
+        source: NodeIndex,
+        target: NodeIndex,
+        relation: Relation,
+        max_hops: u8,
+    ) -> usize {
+        basic::count_matches_in_hops(self, source, target, relation, max_hops)
+    }
+
+    /// Counts the trust score between source and target within max_hops
+    /// distance. Same as [`WotGraph::dump_wot`].
+    #[inline]
+    pub fn dump_wot(&self, source: NodeIndex, target: NodeIndex, max_hops: u8) -> isize {
+        dump_wot::score(
+            self.count_neighbors_in_hops(source, target, Relation::Follow, max_hops),
+            self.count_neighbors_in_hops(source, target, Relation::Mute, max_hops),
+        )
+    }
+}
+
+impl From<&WotGraph> for FrozenWotGraph {
+    #[inline]
+    fn from(graph: &WotGraph) -> Self {
+        Self::new(graph)
+    }
+}
+
+impl Adjacency for FrozenWotGraph {
+    #[inline]
+    fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    #[inline]
+    fn relation_neighbors(
+        &self,
+        node: NodeIndex,
+        relation: Relation,
+        direction: Direction,
+    ) -> impl Iterator<Item = NodeIndex> {
+        self.neighbors(node, relation, direction)
+    }
+}
@@ -0,0 +1,91 @@
+// Copyright (c) 2026, Awiteb <a@4rs.nl>
+//     lightweight nostr Web of Trust library
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use nostr::key::PublicKey;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::GraphSerializationError,
+    options::ImportOptions,
+    parser::GraphBuilder,
+    relations::Relation,
+    utils,
+};
+
+/// A graph in the JSON format.
+#[derive(Serialize, Deserialize)]
+struct JsonGraph {
+    /// The node keys.
+    nodes: Vec<String>,
+    /// The edges between the node keys.
+    edges: Vec<JsonEdge>,
+}
+
+/// An edge in the JSON format.
+#[derive(Serialize, Deserialize)]
+struct JsonEdge {
+    from:     String,
+    to:       String,
+    relation: String,
+}
+
+/// Exports the graph as pretty printed JSON, the nodes of the `known` public
+/// keys are written as hex public keys.
+pub fn export_graph(
+    graph: &crate::GraphType,
+    known: &[PublicKey],
+) -> Result<String, crate::error::Error> {
+    let known = utils::known_keys(known);
+    let nodes: Vec<String> = graph
+        .raw_nodes()
+        .iter()
+        .map(|node| utils::node_key(&known, node.weight))
+        .collect();
+
+    let edges = graph
+        .raw_edges()
+        .iter()
+        .map(|edge| {
+            Ok(JsonEdge {
+                from:     nodes[edge.source().index()].clone(),
+                to:       nodes[edge.target().index()].clone(),
+                relation: Relation::try_from(edge.weight)?.to_string(),
+            })
+        })
+        .collect::<Result<_, GraphSerializationError>>()?;
+
+    Ok(serde_json::to_string_pretty(&JsonGraph { nodes, edges })?)
+}
+
+/// Imports a graph from JSON using the given options. The duplicate nodes are
+/// merged or rejected based on the duplicate policy.
+pub fn import_graph(
+    data: &str,
+    options: &ImportOptions,
+) -> Result<crate::GraphType, crate::error::Error> {
+    let json: JsonGraph = serde_json::from_str(data)?;
+    let mut graph = GraphBuilder::with_counts(json.nodes.len(), json.edges.len(), options)?;
+
+    for key in &json.nodes {
+        graph.add_node(utils::parse_node_key(key)?)?;
+    }
+
+    for edge in &json.edges {
+        let relation: Relation = edge.relation.parse()?;
+        graph.add_edge(
+            utils::parse_node_key(&edge.from)?,
+            relation as u8,
+            utils::parse_node_key(&edge.to)?,
+        )?;
+    }
+
+    graph.finish()
+}
@@ -11,6 +11,10 @@
 
 #![cfg_attr(not(doctest), doc = include_str!("../README.md"))]
 
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicU64, Ordering},
+};
 #[cfg(not(any(target_arch = "wasm32", target_arch = "wasm64")))]
 use std::{
     fs::File,
@@ -18,7 +22,7 @@ use std::{
     path::Path,
 };
 
-use flate2::{Compression, read::GzDecoder, write::GzEncoder};
+use flate2::read::GzDecoder;
 use nostr::key::PublicKey;
 use petgraph::{
     Direction,
@@ -26,14 +30,43 @@ use petgraph::{
     visit::EdgeRef,
 };
 
-pub(crate) const COMPRESSION_LEVEL: Compression = Compression::new(4);
-
+/// Arrow and Parquet export
+#[cfg(feature = "arrow")]
+mod arrow;
+/// Async graph serialization and deserialization
+#[cfg(feature = "tokio")]
+mod async_parser;
+/// CSV edge list import and export
+#[cfg(feature = "csv")]
+mod edge_list;
 /// Library errors
 pub mod error;
+/// GraphML, GEXF and DOT export
+mod formats;
+/// Immutable CSR graph
+pub mod frozen;
+/// JSON import and export
+#[cfg(feature = "json")]
+mod json;
+/// Zero-copy frozen graph
+pub mod mapped;
+/// Graph merging
+mod merge;
+/// Import and export options
+pub mod options;
 /// Graph serialization and deserialization
 mod parser;
+/// Graph snapshots patches
+pub mod patch;
+/// Progress reporting and cancellation
+pub mod progress;
 /// Graph relations
 pub mod relations;
+/// Serde support
+#[cfg(feature = "serde")]
+mod serialization;
+/// Cached source queries
+pub mod source_view;
 /// Extension traits for [`petgraph::graph::DiGraph<u64, u8>`]
 pub mod traits;
 /// Utils
@@ -46,47 +79,104 @@ mod tests;
 /// WoT graph. storing public key hashes and their relations.
 pub(crate) type GraphType = DiGraph<u64, u8>;
 
+/// The identity of the next created graph.
+static NEXT_GRAPH_ID: AtomicU64 = AtomicU64::new(0);
+
 /// A directed graph representing a Web of Trust.
-#[derive(Default)]
 pub struct WotGraph {
     /// The underlying directed graph.
-    pub(crate) inner: GraphType,
+    pub(crate) inner:    GraphType,
+    /// Unique identity of the graph, the [`source_view::SourceView`]s of
+    /// another graph are stale.
+    pub(crate) id:       u64,
+    /// Bumped on every mutable access to the inner graph, used to invalidate
+    /// the [`source_view::SourceView`]s.
+    pub(crate) revision: u64,
+}
+
+impl Default for WotGraph {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl WotGraph {
-    /// Creates a new empty graph.
+    /// Wraps the inner graph with a new identity.
     #[inline]
-    pub fn new() -> Self {
+    pub(crate) fn from_inner(inner: GraphType) -> Self {
         Self {
-            inner: DiGraph::new(),
+            inner,
+            id: NEXT_GRAPH_ID.fetch_add(1, Ordering::Relaxed),
+            revision: 0,
         }
     }
 
+    /// Creates a new empty graph.
+    #[inline]
+    pub fn new() -> Self {
+        Self::from_inner(DiGraph::new())
+    }
+
     /// Creates a new empty graph with preallocated capacity for nodes and
     /// edges.
     #[inline]
     pub fn with_capacity(nodes: usize, edges: usize) -> Self {
-        Self {
-            inner: DiGraph::with_capacity(nodes, edges),
-        }
+        Self::from_inner(DiGraph::with_capacity(nodes, edges))
     }
 
     /// Imports a graph from bytes. The graph should be previously exported
     /// using [`WotGraph::export`].
+    ///
+    /// Graphs exported before the format versioning (without magic bytes) are
+    /// still supported. Returns
+    /// [`error::GraphSerializationError::NotAWotGraph`] if the data is not
+    /// a graph, e.g. a gzip-compressed graph,
+    /// [`error::GraphSerializationError::UnsupportedVersion`] if it's exported
+    /// by a newer version of the library and
+    /// [`error::GraphSerializationError::ChecksumMismatch`] if it's corrupted.
+    ///
+    /// The default [`options::ImportOptions`] have no limits, use
+    /// [`WotGraph::import_with`] for untrusted data.
     #[inline]
     pub fn import(data: &[u8]) -> Result<Self, error::Error> {
-        Ok(Self {
-            inner: parser::import_graph(data)?,
-        })
+        Self::import_with(data, &options::ImportOptions::default())
+    }
+
+    /// Imports a graph from bytes using the given options, see
+    /// [`WotGraph::import`].
+    ///
+    /// Returns [`error::GraphSerializationError::LimitExceeded`] if the graph
+    /// has more nodes or edges than the options limits. Nodes of the same
+    /// public key hash are merged, or rejected with
+    /// [`error::GraphSerializationError::DuplicateNode`], based on
+    /// [`options::ImportOptions::duplicates`].
+    #[inline]
+    pub fn import_with(
+        data: &[u8],
+        options: &options::ImportOptions,
+    ) -> Result<Self, error::Error> {
+        Ok(Self::from_inner(parser::import_graph(data, options)?))
     }
 
     /// Imports a graph from a gzip-compressed bytes. The graph should be
     /// previously exported using [`WotGraph::export_gzip`].
     #[inline]
     pub fn import_gzip(data: &[u8]) -> Result<Self, error::Error> {
-        Ok(Self {
-            inner: parser::import_graph(GzDecoder::new(data))?,
-        })
+        Self::import_gzip_with(data, &options::ImportOptions::default())
+    }
+
+    /// Imports a graph from a gzip-compressed bytes using the given options,
+    /// see [`WotGraph::import_with`].
+    #[inline]
+    pub fn import_gzip_with(
+        data: &[u8],
+        options: &options::ImportOptions,
+    ) -> Result<Self, error::Error> {
+        Ok(Self::from_inner(parser::import_graph(
+            GzDecoder::new(data),
+            options,
+        )?))
     }
 
     /// Import a graph from a file. Must be exported using
@@ -94,9 +184,21 @@ impl WotGraph {
     #[cfg(not(any(target_arch = "wasm32", target_arch = "wasm64")))]
     #[inline]
     pub fn import_from_file<P: AsRef<Path>>(path: P) -> Result<Self, error::Error> {
-        Ok(Self {
-            inner: parser::import_graph(BufReader::new(File::open(path)?))?,
-        })
+        Self::import_from_file_with(path, &options::ImportOptions::default())
+    }
+
+    /// Import a graph from a file using the given options, see
+    /// [`WotGraph::import_with`].
+    #[cfg(not(any(target_arch = "wasm32", target_arch = "wasm64")))]
+    #[inline]
+    pub fn import_from_file_with<P: AsRef<Path>>(
+        path: P,
+        options: &options::ImportOptions,
+    ) -> Result<Self, error::Error> {
+        Ok(Self::from_inner(parser::import_graph(
+            BufReader::new(File::open(path)?),
+            options,
+        )?))
     }
 
     /// Import a gzip compressed graph from a file. Must be exported using
@@ -104,9 +206,61 @@ impl WotGraph {
     #[cfg(not(any(target_arch = "wasm32", target_arch = "wasm64")))]
     #[inline]
     pub fn import_from_file_gzip<P: AsRef<Path>>(path: P) -> Result<Self, error::Error> {
-        Ok(Self {
-            inner: parser::import_graph(GzDecoder::new(File::open(path)?))?,
-        })
+        Self::import_from_file_gzip_with(path, &options::ImportOptions::default())
+    }
+
+    /// Import a gzip compressed graph from a file using the given options, see
+    /// [`WotGraph::import_with`].
+    #[cfg(not(any(target_arch = "wasm32", target_arch = "wasm64")))]
+    #[inline]
+    pub fn import_from_file_gzip_with<P: AsRef<Path>>(
+        path: P,
+        options: &options::ImportOptions,
+    ) -> Result<Self, error::Error> {
+        Ok(Self::from_inner(parser::import_graph(
+            GzDecoder::new(File::open(path)?),
+            options,
+        )?))
+    }
+
+    /// Imports a graph from bytes, compressed or not. The compression codec is
+    /// detected from the data, see [`WotGraph::import`] and
+    /// [`options::Codec`].
+    #[inline]
+    pub fn import_auto(data: &[u8]) -> Result<Self, error::Error> {
+        Self::import_auto_with(data, &options::ImportOptions::default())
+    }
+
+    /// Imports a graph from bytes, compressed or not, using the given options,
+    /// see [`WotGraph::import_with`].
+    #[inline]
+    pub fn import_auto_with(
+        data: &[u8],
+        options: &options::ImportOptions,
+    ) -> Result<Self, error::Error> {
+        Ok(Self::from_inner(parser::import_graph_auto(data, options)?))
+    }
+
+    /// Import a graph from a file, compressed or not. The compression codec is
+    /// detected from the file content, not its extension.
+    #[cfg(not(any(target_arch = "wasm32", target_arch = "wasm64")))]
+    #[inline]
+    pub fn import_from_file_auto<P: AsRef<Path>>(path: P) -> Result<Self, error::Error> {
+        Self::import_from_file_auto_with(path, &options::ImportOptions::default())
+    }
+
+    /// Import a graph from a file, compressed or not, using the given options,
+    /// see [`WotGraph::import_with`].
+    #[cfg(not(any(target_arch = "wasm32", target_arch = "wasm64")))]
+    #[inline]
+    pub fn import_from_file_auto_with<P: AsRef<Path>>(
+        path: P,
+        options: &options::ImportOptions,
+    ) -> Result<Self, error::Error> {
+        Ok(Self::from_inner(parser::import_graph_auto(
+            BufReader::new(File::open(path)?),
+            options,
+        )?))
     }
 
     /// The inner [`petgraph::Graph`] instance.
@@ -116,8 +270,11 @@ impl WotGraph {
     }
 
     /// The mutable inner [`petgraph::Graph`] instance.
+    ///
+    /// This invalidates all the [`source_view::SourceView`]s of the graph.
     #[inline]
     pub fn inner_mut(&mut self) -> &mut GraphType {
+        self.revision = self.revision.wrapping_add(1);
         &mut self.inner
     }
 
@@ -260,23 +417,41 @@ impl WotGraph {
     }
 
     /// Calculates the total number of bytes needed for exporting the graph.
-    fn export_capacity(&self) -> usize {
-        32 + (self.inner.raw_nodes().len() * 8) + (self.inner.raw_edges().len() * 17)
+    fn export_capacity(&self, options: &options::ExportOptions) -> usize {
+        let nodes = self.inner.raw_nodes().len() * 8;
+        match options.encoding {
+            options::Encoding::Standard => 48 + nodes + (self.inner.raw_edges().len() * 17),
+            // Most of the varint deltas fit in two bytes
+            options::Encoding::Compact => 56 + nodes + (self.inner.raw_edges().len() * 2),
+        }
     }
 
     /// Export the graph nodes and edges in a binary format (little-endian).
     ///
     /// Format:
+    /// - 4 bytes: magic `NWOT`
+    /// - 4 bytes: format version
     /// - 8 bytes: nodes capacity
     /// - 8 bytes: edges capacity
     /// - 8 bytes: number of nodes
     /// - 8 bytes: number of edges
     /// - N * 8 bytes: node weights
     /// - E * 17 bytes: edges (8 bytes source, 1 byte relation, 8 bytes target)
+    /// - 8 bytes: xxh64 checksum of all the previous bytes
     #[inline]
     pub fn export(&self) -> Result<Vec<u8>, error::Error> {
-        let mut buffer = Vec::with_capacity(self.export_capacity());
-        parser::export_graph(&self.inner, &mut buffer)?;
+        self.export_with(&options::ExportOptions::default())
+    }
+
+    /// Export the graph using the given options, see [`WotGraph::export`].
+    ///
+    /// With [`Encoding::Compact`](options::Encoding::Compact) the edges are
+    /// stored as varint node indices grouped by source and relation, so the
+    /// imported graph has the same nodes but its edges are sorted by source,
+    /// relation and target.
+    pub fn export_with(&self, options: &options::ExportOptions) -> Result<Vec<u8>, error::Error> {
+        let mut buffer = Vec::with_capacity(self.export_capacity(options));
+        parser::export_graph(&self.inner, &mut buffer, options)?;
         Ok(buffer)
     }
 
@@ -284,20 +459,43 @@ impl WotGraph {
     ///
     /// The output is a compressed version of the data from
     /// [`WotGraph::export`].
+    #[inline]
     pub fn export_gzip(&self) -> Result<Vec<u8>, error::Error> {
-        let mut compressed_graph = Vec::with_capacity(self.export_capacity() / 2);
-        let mut encoder = GzEncoder::new(&mut compressed_graph, COMPRESSION_LEVEL);
-        parser::export_graph(&self.inner, &mut encoder)?;
-        encoder.finish()?;
+        self.export_compressed_with(&options::ExportOptions::default())
+    }
+
+    /// Compresses the graph exported using the given options with the options
+    /// codec and level, see [`WotGraph::export_with`].
+    ///
+    /// Use [`WotGraph::import_auto`] to import it, or [`WotGraph::import_gzip`]
+    /// for the gzip codec.
+    pub fn export_compressed_with(
+        &self,
+        options: &options::ExportOptions,
+    ) -> Result<Vec<u8>, error::Error> {
+        let mut compressed_graph = Vec::with_capacity(self.export_capacity(options) / 2);
+        parser::export_compressed(&self.inner, &mut compressed_graph, options)?;
 
         Ok(compressed_graph)
     }
 
     /// Export the graph to a file.
     #[cfg(not(any(target_arch = "wasm32", target_arch = "wasm64")))]
+    #[inline]
     pub fn export_to_file<P: AsRef<Path>>(&self, path: P) -> Result<(), error::Error> {
+        self.export_to_file_with(path, &options::ExportOptions::default())
+    }
+
+    /// Export the graph to a file using the given options, see
+    /// [`WotGraph::export_with`].
+    #[cfg(not(any(target_arch = "wasm32", target_arch = "wasm64")))]
+    pub fn export_to_file_with<P: AsRef<Path>>(
+        &self,
+        path: P,
+        options: &options::ExportOptions,
+    ) -> Result<(), error::Error> {
         let mut writer = BufWriter::new(File::create(path)?);
-        parser::export_graph(&self.inner, &mut writer)?;
+        parser::export_graph(&self.inner, &mut writer, options)?;
         writer.flush()?;
 
         Ok(())
@@ -305,17 +503,487 @@ impl WotGraph {
 
     /// Export a gzip compressed graph to file.
     #[cfg(not(any(target_arch = "wasm32", target_arch = "wasm64")))]
+    #[inline]
     pub fn export_to_file_gzip<P: AsRef<Path>>(&self, path: P) -> Result<(), error::Error> {
-        let mut file = BufWriter::new(File::create(path)?);
-        let mut encoder = GzEncoder::new(&mut file, COMPRESSION_LEVEL);
+        self.export_to_file_compressed_with(path, &options::ExportOptions::default())
+    }
 
-        parser::export_graph(&self.inner, &mut encoder)?;
-        encoder.finish()?;
+    /// Export a compressed graph to file using the given options, see
+    /// [`WotGraph::export_compressed_with`].
+    #[cfg(not(any(target_arch = "wasm32", target_arch = "wasm64")))]
+    pub fn export_to_file_compressed_with<P: AsRef<Path>>(
+        &self,
+        path: P,
+        options: &options::ExportOptions,
+    ) -> Result<(), error::Error> {
+        let mut file = BufWriter::new(File::create(path)?);
+        parser::export_compressed(&self.inner, &mut file, options)?;
         file.flush()?;
 
         Ok(())
     }
 
+    /// Imports a graph from a CSV edge list of `follower,followee,relation`
+    /// records, streamed from the reader.
+    ///
+    /// The keys can be hex or npub public keys, or 16 hex hashes, and the
+    /// relations `follow` or `mute`. A `follower,followee,relation` header is
+    /// skipped.
+    #[cfg(feature = "csv")]
+    #[inline]
+    pub fn import_csv<R: std::io::Read>(reader: R) -> Result<Self, error::Error> {
+        Self::import_csv_with(reader, &options::ImportOptions::default())
+    }
+
+    /// Imports a graph from a CSV edge list using the given options, see
+    /// [`WotGraph::import_csv`].
+    ///
+    /// The size of the list is unknown until its end, so the limits are
+    /// checked as the records are read and the progress totals are the
+    /// processed counts. The nodes come from the edges, so there are no
+    /// duplicate nodes.
+    #[cfg(feature = "csv")]
+    #[inline]
+    pub fn import_csv_with<R: std::io::Read>(
+        reader: R,
+        options: &options::ImportOptions,
+    ) -> Result<Self, error::Error> {
+        Ok(Self::from_inner(edge_list::import_graph(reader, options)?))
+    }
+
+    /// Imports a graph from JSON, see [`WotGraph::export_json`] for the format.
+    ///
+    /// The node keys can be hex or npub public keys, or 16 hex hashes.
+    /// Returns [`error::GraphSerializationError::UnknownRelation`] for a
+    /// relation other than `follow` and `mute`.
+    #[cfg(feature = "json")]
+    #[inline]
+    pub fn import_json(data: &str) -> Result<Self, error::Error> {
+        Self::import_json_with(data, &options::ImportOptions::default())
+    }
+
+    /// Imports a graph from JSON using the given options, see
+    /// [`WotGraph::import_json`]. The node and edge limits are checked against
+    /// the parsed lists before building the graph.
+    #[cfg(feature = "json")]
+    #[inline]
+    pub fn import_json_with(
+        data: &str,
+        options: &options::ImportOptions,
+    ) -> Result<Self, error::Error> {
+        Ok(Self::from_inner(json::import_graph(data, options)?))
+    }
+
+    /// Deserializes a graph using the import options, unlike its
+    /// `Deserialize` implementation which keeps the graph as is. The limits
+    /// are checked once the data is deserialized, before building the graph,
+    /// and the duplicate nodes are merged or rejected as with
+    /// [`WotGraph::import_with`].
+    ///
+    /// ```rust
+    /// use nostr_wot::{WotGraph, options::ImportOptions};
+    ///
+    /// let json = r#"{"nodes":[1,2,3],"edges":[[0,"follow",1]]}"#;
+    /// let options = ImportOptions::default().max_nodes(2);
+    /// let mut deserializer = serde_json::Deserializer::from_str(json);
+    /// assert!(WotGraph::deserialize_with(&mut deserializer, &options).is_err());
+    /// ```
+    #[cfg(feature = "serde")]
+    #[inline]
+    pub fn deserialize_with<'de, D: serde::Deserializer<'de>>(
+        deserializer: D,
+        options: &options::ImportOptions,
+    ) -> Result<Self, D::Error> {
+        serialization::deserialize_graph(deserializer, options).map(Self::from_inner)
+    }
+
+    /// Imports a graph from an async reader, raw or gzip-compressed, using the
+    /// given options. The records are streamed from the reader, see
+    /// [`WotGraph::import_auto_with`]. Codecs other than gzip are not
+    /// supported.
+    #[cfg(feature = "tokio")]
+    pub async fn import_async<R: tokio::io::AsyncRead + Unpin>(
+        data: R,
+        options: &options::ImportOptions,
+    ) -> Result<Self, error::Error> {
+        Ok(Self::from_inner(
+            async_parser::import_graph_auto(data, options).await?,
+        ))
+    }
+
+    /// Imports a graph from a file, raw or gzip-compressed, without blocking
+    /// the executor. See [`WotGraph::import_async`].
+    #[cfg(all(
+        feature = "tokio",
+        not(any(target_arch = "wasm32", target_arch = "wasm64"))
+    ))]
+    pub async fn import_from_file_async<P: AsRef<Path>>(
+        path: P,
+        options: &options::ImportOptions,
+    ) -> Result<Self, error::Error> {
+        Self::import_async(tokio::fs::File::open(path).await?, options).await
+    }
+
+    /// Exports the graph as human-readable JSON, for debugging and sharing
+    /// small graphs.
+    ///
+    /// The graph only stores the public key hashes, so the nodes of the
+    /// `known` public keys are written as hex public keys and the others as
+    /// their hash in 16 hex characters. The edges are
+    /// `{"from": key, "to": key, "relation": "follow" | "mute"}` objects.
+    ///
+    /// ```rust
+    /// use nostr::key::Keys;
+    /// use nostr_wot::{WotGraph, relations::Relation};
+    ///
+    /// let pkey = Keys::generate().public_key();
+    /// let mut graph = WotGraph::new();
+    /// let node1 = graph.add_node_pkey(&pkey).unwrap();
+    /// let node2 = graph.add_node(2).unwrap();
+    /// graph.add_edge(node1, node2, Relation::Mute).unwrap();
+    ///
+    /// let json = graph.export_json(&[pkey]).unwrap();
+    /// assert!(json.contains(&pkey.to_hex()));
+    /// assert!(json.contains(r#""to": "0000000000000002""#));
+    ///
+    /// let graph = WotGraph::import_json(&json).unwrap();
+    /// assert_eq!(graph.node_index(&pkey), Some(node1));
+    /// ```
+    #[cfg(feature = "json")]
+    #[inline]
+    pub fn export_json(&self, known: &[PublicKey]) -> Result<String, error::Error> {
+        json::export_graph(&self.inner, known)
+    }
+
+    /// Exports the graph edges as CSV `follower,followee,relation` records,
+    /// with a header. The nodes of the `known` public keys are written as hex
+    /// public keys and the others as their hash in 16 hex characters.
+    ///
+    /// ```rust
+    /// use nostr_wot::{WotGraph, relations::Relation};
+    ///
+    /// let mut graph = WotGraph::new();
+    /// let node1 = graph.add_node(1).unwrap();
+    /// let node2 = graph.add_node(2).unwrap();
+    /// graph.add_edge(node1, node2, Relation::Follow).unwrap();
+    ///
+    /// let mut csv = Vec::new();
+    /// graph.export_csv(&mut csv, &[]).unwrap();
+    /// assert_eq!(
+    ///     String::from_utf8(csv).unwrap(),
+    ///     "follower,followee,relation\n0000000000000001,0000000000000002,follow\n"
+    /// );
+    /// ```
+    #[cfg(feature = "csv")]
+    #[inline]
+    pub fn export_csv<W: std::io::Write>(
+        &self,
+        writer: W,
+        known: &[PublicKey],
+    ) -> Result<(), error::Error> {
+        edge_list::export_graph(&self.inner, writer, known)
+    }
+
+    /// Returns the graph nodes as an Arrow record batch, with an `index`
+    /// (`UInt32`), `hash` (`UInt64`) and `pubkey` (`Utf8`, the hex public key
+    /// of the `known` nodes, null otherwise) columns. The scores of the
+    /// options are added as `page_rank` (`Float64`) and `dump_wot` (`Int64`)
+    /// columns. Returns [`error::Error::InvalidDampingFactor`] if the PageRank
+    /// damping factor is not between 0 and 1.
+    #[cfg(feature = "arrow")]
+    #[inline]
+    pub fn nodes_record_batch(
+        &self,
+        known: &[PublicKey],
+        options: &options::AttributeOptions,
+    ) -> Result<arrow_array::RecordBatch, error::Error> {
+        arrow::nodes_batch(self, known, options)
+    }
+
+    /// Returns the graph edges as an Arrow record batch, with a `source` and
+    /// `target` (`UInt32`, the node indices) and `relation` (`Utf8`, `follow`
+    /// or `mute`) columns.
+    #[cfg(feature = "arrow")]
+    #[inline]
+    pub fn edges_record_batch(&self) -> Result<arrow_array::RecordBatch, error::Error> {
+        arrow::edges_batch(self)
+    }
+
+    /// Exports the nodes and edges tables as two Parquet files, see
+    /// [`WotGraph::nodes_record_batch`] and [`WotGraph::edges_record_batch`].
+    /// The tables join on `nodes.index` and `edges.source` or `edges.target`.
+    ///
+    /// ```rust
+    /// use nostr_wot::{WotGraph, options::AttributeOptions, relations::Relation};
+    ///
+    /// let mut graph = WotGraph::new();
+    /// let node1 = graph.add_node(1).unwrap();
+    /// let node2 = graph.add_node(2).unwrap();
+    /// graph.add_edge(node1, node2, Relation::Follow).unwrap();
+    ///
+    /// let (mut nodes, mut edges) = (Vec::new(), Vec::new());
+    /// let options = AttributeOptions::default().page_rank(0.85, 20);
+    /// graph
+    ///     .export_parquet(&mut nodes, &mut edges, &[], &options)
+    ///     .unwrap();
+    /// assert!(nodes.starts_with(b"PAR1"));
+    /// ```
+    #[cfg(feature = "arrow")]
+    pub fn export_parquet<N: std::io::Write + Send, E: std::io::Write + Send>(
+        &self,
+        nodes: N,
+        edges: E,
+        known: &[PublicKey],
+        options: &options::AttributeOptions,
+    ) -> Result<(), error::Error> {
+        arrow::write_parquet(nodes, &self.nodes_record_batch(known, options)?)?;
+        arrow::write_parquet(edges, &self.edges_record_batch()?)
+    }
+
+    /// Exports the graph as GraphML, e.g. for Gephi.
+    ///
+    /// The nodes have a `hash` attribute (16 hex characters) and the edges a
+    /// `relation` attribute (`follow` or `mute`). The scores of the options
+    /// are added as node attributes. Returns
+    /// [`error::Error::InvalidDampingFactor`] if the PageRank damping factor
+    /// is not between 0 and 1.
+    ///
+    /// ```rust
+    /// use nostr_wot::{WotGraph, options::AttributeOptions, relations::Relation};
+    ///
+    /// let mut graph = WotGraph::new();
+    /// let node1 = graph.add_node(1).unwrap();
+    /// let node2 = graph.add_node(2).unwrap();
+    /// graph.add_edge(node1, node2, Relation::Follow).unwrap();
+    ///
+    /// let options = AttributeOptions::default()
+    ///     .page_rank(0.85, 20)
+    ///     .dump_wot(node1, 3);
+    /// let mut graphml = Vec::new();
+    /// graph.export_graphml(&mut graphml, &options).unwrap();
+    /// assert!(
+    ///     String::from_utf8(graphml)
+    ///         .unwrap()
+    ///         .contains(r#"<data key="relation">follow</data>"#)
+    /// );
+    /// ```
+    #[inline]
+    pub fn export_graphml<W: std::io::Write>(
+        &self,
+        writer: W,
+        options: &options::AttributeOptions,
+    ) -> Result<(), error::Error> {
+        formats::export_graphml(self, writer, options)
+    }
+
+    /// Exports the graph as GEXF 1.3, e.g. for Gephi. Same attributes as
+    /// [`WotGraph::export_graphml`], the node labels are their hashes and the
+    /// edge labels their relations.
+    #[inline]
+    pub fn export_gexf<W: std::io::Write>(
+        &self,
+        writer: W,
+        options: &options::AttributeOptions,
+    ) -> Result<(), error::Error> {
+        formats::export_gexf(self, writer, options)
+    }
+
+    /// Exports the network of `source` within `max_hops` as Graphviz DOT, to
+    /// visualize why a target is trusted.
+    ///
+    /// The nodes are the source's network following the `Follow` edges and
+    /// the target, with every edge between them. `Follow` edges are green and
+    /// `Mute` edges red and dashed, the source is highlighted in blue and the
+    /// target in gold.
+    ///
+    /// ```rust
+    /// use nostr_wot::{WotGraph, relations::Relation};
+    ///
+    /// let mut graph = WotGraph::new();
+    /// let node1 = graph.add_node(1).unwrap();
+    /// let node2 = graph.add_node(2).unwrap();
+    /// let node3 = graph.add_node(3).unwrap();
+    /// graph.add_edge(node1, node2, Relation::Follow).unwrap();
+    /// graph.add_edge(node2, node3, Relation::Mute).unwrap();
+    ///
+    /// let mut dot = Vec::new();
+    /// graph.export_dot(&mut dot, node1, 1, Some(node3)).unwrap();
+    /// let dot = String::from_utf8(dot).unwrap();
+    /// assert!(dot.contains(r#"n1 -> n2 [color="red", style=dashed, label="mute"];"#));
+    /// ```
+    #[inline]
+    pub fn export_dot<W: std::io::Write>(
+        &self,
+        writer: W,
+        source: NodeIndex,
+        max_hops: u8,
+        target: Option<NodeIndex>,
+    ) -> Result<(), error::Error> {
+        formats::export_dot(self, writer, source, max_hops, target)
+    }
+
+    /// Returns the patch turning this graph into `other`, the added and
+    /// removed nodes and edges by public key hash. See [`patch::GraphPatch`].
+    ///
+    /// Useful to ship only the changes between two snapshots, the patch is
+    /// exported with [`patch::GraphPatch::export`] and applied to the older
+    /// snapshot with [`WotGraph::apply_patch`].
+    ///
+    /// ```rust
+    /// use nostr_wot::{WotGraph, patch::GraphPatch, relations::Relation};
+    ///
+    /// let mut old = WotGraph::new();
+    /// let node1 = old.add_node(1).unwrap();
+    /// let node2 = old.add_node(2).unwrap();
+    /// old.add_edge(node1, node2, Relation::Follow).unwrap();
+    ///
+    /// let mut new = WotGraph::new();
+    /// let node2 = new.add_node(2).unwrap();
+    /// let node3 = new.add_node(3).unwrap();
+    /// let node1 = new.add_node(1).unwrap();
+    /// new.add_edge(node1, node2, Relation::Follow).unwrap();
+    /// new.add_edge(node2, node3, Relation::Mute).unwrap();
+    ///
+    /// let patch = GraphPatch::import(&old.diff(&new).export().unwrap()).unwrap();
+    /// assert_eq!(patch.added_edges, [(2, Relation::Mute, 3)]);
+    ///
+    /// old.apply_patch(&patch).unwrap();
+    /// assert!(old.diff(&new).is_empty());
+    /// ```
+    #[inline]
+    pub fn diff(&self, other: &Self) -> patch::GraphPatch {
+        patch::diff(&self.inner, &other.inner)
+    }
+
+    /// Applies a patch created by [`WotGraph::diff`]. The added edges already
+    /// in the graph are skipped, and removing a node removes its edges and
+    /// every node of the same hash.
+    ///
+    /// Removing nodes and edges changes the indices of the last ones, so the
+    /// indices held before the patch may be invalid. Returns
+    /// [`error::GraphSerializationError::NodeNotFound`] if an added edge
+    /// references a node that's neither in the graph nor added, the graph is
+    /// unchanged then. Returns [`error::Error::Graph`] if the graph is full,
+    /// after removing the nodes and edges of the patch.
+    #[inline]
+    pub fn apply_patch(&mut self, patch: &patch::GraphPatch) -> Result<(), error::Error> {
+        patch::apply(self, patch)
+    }
+
+    /// Merges `other` into the graph, e.g. graphs crawled from different
+    /// relays. The nodes are unioned by public key hash, and the edges of
+    /// `other` not already in the graph are added.
+    ///
+    /// See [`WotGraph::merge_with`] to keep the newer lists on conflict.
+    ///
+    /// ```rust
+    /// use nostr_wot::{WotGraph, relations::Relation};
+    ///
+    /// let mut graph = WotGraph::new();
+    /// let node1 = graph.add_node(1).unwrap();
+    /// let node2 = graph.add_node(2).unwrap();
+    /// graph.add_edge(node1, node2, Relation::Follow).unwrap();
+    ///
+    /// let mut other = WotGraph::new();
+    /// let node3 = other.add_node(3).unwrap();
+    /// let node1 = other.add_node(1).unwrap();
+    /// let node2 = other.add_node(2).unwrap();
+    /// other.add_edge(node1, node2, Relation::Follow).unwrap();
+    /// other.add_edge(node2, node3, Relation::Follow).unwrap();
+    ///
+    /// graph.merge(&other);
+    /// assert_eq!(graph.inner().node_count(), 3);
+    /// assert_eq!(graph.inner().edge_count(), 2);
+    /// ```
+    #[inline]
+    pub fn merge(&mut self, other: &Self) {
+        merge::merge(self, other, |_, _| merge::Resolution::Union);
+    }
+
+    /// Same as [`WotGraph::merge`], but resolves the conflicting lists with
+    /// the timestamps of the replaceable list events.
+    ///
+    /// `timestamps` and `other_timestamps` return the `created_at` of an
+    /// author list in the graph and in `other`, by the author public key hash
+    /// (see [`utils::hash_bytes`]) and the list relation. They are called for
+    /// every author in either graph. When both timestamps of a list are
+    /// known, the newer list is kept (the graph one on a tie), otherwise the
+    /// lists are unioned.
+    ///
+    /// An author without edges of a relation has an empty list, so a newer
+    /// emptied list replaces the older one.
+    pub fn merge_with<A, B>(&mut self, other: &Self, timestamps: A, other_timestamps: B)
+    where
+        A: Fn(u64, relations::Relation) -> Option<u64>,
+        B: Fn(u64, relations::Relation) -> Option<u64>,
+    {
+        merge::merge(self, other, |author, relation| {
+            match (
+                timestamps(author, relation),
+                other_timestamps(author, relation),
+            ) {
+                (Some(timestamp), Some(other_timestamp)) if other_timestamp > timestamp => {
+                    merge::Resolution::Replace
+                }
+                (Some(_), Some(_)) => merge::Resolution::Keep,
+                _ => merge::Resolution::Union,
+            }
+        });
+    }
+
+    /// Exports the graph to an async writer using the given options. The
+    /// records are streamed to the writer, see [`WotGraph::export_with`].
+    #[cfg(feature = "tokio")]
+    pub async fn export_async<W: tokio::io::AsyncWrite + Unpin>(
+        &self,
+        writer: &mut W,
+        options: &options::ExportOptions,
+    ) -> Result<(), error::Error> {
+        async_parser::export_graph(&self.inner, writer, options).await
+    }
+
+    /// Exports the graph compressed with gzip to an async writer using the
+    /// given options, the options codec is ignored. The writer is shut down
+    /// to finish the gzip stream. See [`WotGraph::export_async`].
+    #[cfg(feature = "tokio")]
+    pub async fn export_gzip_async<W: tokio::io::AsyncWrite + Unpin>(
+        &self,
+        writer: &mut W,
+        options: &options::ExportOptions,
+    ) -> Result<(), error::Error> {
+        async_parser::export_gzip(&self.inner, writer, options).await
+    }
+
+    /// Exports the graph to a file without blocking the executor. See
+    /// [`WotGraph::export_async`].
+    #[cfg(all(
+        feature = "tokio",
+        not(any(target_arch = "wasm32", target_arch = "wasm64"))
+    ))]
+    pub async fn export_to_file_async<P: AsRef<Path>>(
+        &self,
+        path: P,
+        options: &options::ExportOptions,
+    ) -> Result<(), error::Error> {
+        let mut file = tokio::fs::File::create(path).await?;
+        self.export_async(&mut file, options).await
+    }
+
+    /// Exports the graph compressed with gzip to a file without blocking the
+    /// executor. See [`WotGraph::export_gzip_async`].
+    #[cfg(all(
+        feature = "tokio",
+        not(any(target_arch = "wasm32", target_arch = "wasm64"))
+    ))]
+    pub async fn export_to_file_gzip_async<P: AsRef<Path>>(
+        &self,
+        path: P,
+        options: &options::ExportOptions,
+    ) -> Result<(), error::Error> {
+        let mut file = tokio::fs::File::create(path).await?;
+        self.export_gzip_async(&mut file, options).await
+    }
+
     /// Finds the neighboring nodes of `source` based on the given `relation`
     /// and `direction`.
     ///
@@ -348,7 +1016,8 @@ impl WotGraph {
     /// O(V + E) where V is reachable vertices and E is their edges
     ///
     /// # Space Complexity
-    /// O(V) for visited set and current level storage
+    /// O(N) bits for the visited and target sets, where N is the graph nodes,
+    /// reused between the calls
     #[inline(always)]
     pub fn count_neighbors_in_hops(
         &self,
@@ -375,4 +1044,222 @@ impl WotGraph {
     pub fn dump_wot(&self, source: NodeIndex, target: NodeIndex, max_hops: u8) -> isize {
         traits::dump_wot::DumpWotExt::dump_wot(&self.inner, source, target, max_hops)
     }
+
+    /// Builds an immutable copy of the graph in a compressed sparse row
+    /// layout, faster to query. See [`frozen::FrozenWotGraph`].
+    #[inline]
+    pub fn freeze(&self) -> frozen::FrozenWotGraph {
+        frozen::FrozenWotGraph::new(self)
+    }
+
+    /// Creates a cached view of the source's network within `max_hops`, to
+    /// answer many queries from the same source cheaply. See
+    /// [`source_view::SourceView`].
+    #[inline]
+    pub fn source_view(&self, source: NodeIndex, max_hops: u8) -> source_view::SourceView {
+        source_view::SourceView::new(self, source, max_hops)
+    }
+
+    /// Returns the `k` highest scoring targets by [`WotGraph::dump_wot`]
+    /// within `max_hops` from the source, sorted by score in descending order.
+    ///
+    /// Only targets with a positive score are returned, and the source itself
+    /// is excluded. This is much faster than calling [`WotGraph::dump_wot`]
+    /// for every node, the scores are collected in a single traversal.
+    ///
+    /// ```rust
+    /// use nostr_wot::{WotGraph, relations::Relation};
+    ///
+    /// let mut graph = WotGraph::new();
+    /// let node1 = graph.add_node(1).unwrap();
+    /// let node2 = graph.add_node(2).unwrap();
+    /// let node3 = graph.add_node(3).unwrap();
+    /// let node4 = graph.add_node(4).unwrap();
+    /// graph.add_edge(node1, node2, Relation::Follow).unwrap();
+    /// graph.add_edge(node1, node3, Relation::Follow).unwrap();
+    /// graph.add_edge(node2, node3, Relation::Follow).unwrap();
+    /// graph.add_edge(node2, node4, Relation::Mute).unwrap();
+    ///
+    /// assert_eq!(graph.top_k(node1, 1, 10), vec![(node3, 2), (node2, 1)]);
+    /// ```
+    #[inline(always)]
+    pub fn top_k(&self, source: NodeIndex, max_hops: u8, k: usize) -> Vec<(NodeIndex, isize)> {
+        traits::top_k::TopKExt::top_k(&self.inner, source, max_hops, k)
+    }
+
+    /// Same as [`WotGraph::top_k`], but returns [`error::Error::Cancelled`]
+    /// once the token is cancelled.
+    #[inline(always)]
+    pub fn top_k_cancellable(
+        &self,
+        source: NodeIndex,
+        max_hops: u8,
+        k: usize,
+        cancel: &progress::CancellationToken,
+    ) -> Result<Vec<(NodeIndex, isize)>, error::Error> {
+        traits::top_k::TopKExt::top_k_cancellable(&self.inner, source, max_hops, k, cancel)
+    }
+
+    /// Computes the PageRank of every node over the `Follow` edges, indexed
+    /// by node index. See [`traits::page_rank::PageRankExt::page_rank`].
+    ///
+    /// # Panics
+    /// If `damping_factor` is not between 0 and 1
+    #[inline(always)]
+    pub fn page_rank(&self, damping_factor: f64, iterations: usize) -> Vec<f64> {
+        traits::page_rank::PageRankExt::page_rank(&self.inner, damping_factor, iterations)
+    }
+
+    /// Same as [`WotGraph::page_rank`], but returns [`error::Error::Cancelled`]
+    /// once the token is cancelled.
+    ///
+    /// # Panics
+    /// If `damping_factor` is not between 0 and 1
+    #[inline(always)]
+    pub fn page_rank_cancellable(
+        &self,
+        damping_factor: f64,
+        iterations: usize,
+        cancel: &progress::CancellationToken,
+    ) -> Result<Vec<f64>, error::Error> {
+        traits::page_rank::PageRankExt::page_rank_cancellable(
+            &self.inner,
+            damping_factor,
+            iterations,
+            cancel,
+        )
+    }
+
+    /// Collects, for each source, the nodes within `max_hops` following the
+    /// `Follow` edges with their hop distance, the source itself included at
+    /// hop 0.
+    ///
+    /// The sources are traversed together in batches of 64 (MS-BFS), which is
+    /// much cheaper than a traversal per source when their networks overlap.
+    /// See [`traits::ms_bfs::MultiSourceBfsExt::multi_source_bfs`] to visit
+    /// the nodes without collecting them.
+    ///
+    /// ```rust
+    /// use nostr_wot::{WotGraph, relations::Relation};
+    ///
+    /// let mut graph = WotGraph::new();
+    /// let node1 = graph.add_node(1).unwrap();
+    /// let node2 = graph.add_node(2).unwrap();
+    /// let node3 = graph.add_node(3).unwrap();
+    /// graph.add_edge(node1, node2, Relation::Follow).unwrap();
+    /// graph.add_edge(node2, node3, Relation::Follow).unwrap();
+    ///
+    /// let hops = graph.multi_source_hops(&[node1, node2], 2);
+    /// assert_eq!(hops[0].get(&node3), Some(&2));
+    /// assert_eq!(hops[1].get(&node3), Some(&1));
+    /// assert_eq!(hops[1].get(&node1), None);
+    /// ```
+    #[inline(always)]
+    pub fn multi_source_hops(
+        &self,
+        sources: &[NodeIndex],
+        max_hops: u8,
+    ) -> Vec<HashMap<NodeIndex, u8>> {
+        traits::ms_bfs::MultiSourceBfsExt::multi_source_hops(&self.inner, sources, max_hops)
+    }
+
+    /// Same as [`WotGraph::multi_source_hops`], but returns
+    /// [`error::Error::Cancelled`] once the token is cancelled.
+    #[inline(always)]
+    pub fn multi_source_hops_cancellable(
+        &self,
+        sources: &[NodeIndex],
+        max_hops: u8,
+        cancel: &progress::CancellationToken,
+    ) -> Result<Vec<HashMap<NodeIndex, u8>>, error::Error> {
+        traits::ms_bfs::MultiSourceBfsExt::multi_source_hops_cancellable(
+            &self.inner,
+            sources,
+            max_hops,
+            cancel,
+        )
+    }
+
+    /// Counts the trust score between each source and the target within
+    /// `max_hops`, same as calling [`WotGraph::dump_wot`] for each source but
+    /// traversing the sources together in batches of 64 (MS-BFS).
+    #[inline(always)]
+    pub fn multi_source_dump_wot(
+        &self,
+        sources: &[NodeIndex],
+        target: NodeIndex,
+        max_hops: u8,
+    ) -> Vec<isize> {
+        traits::ms_bfs::MultiSourceBfsExt::multi_source_dump_wot(
+            &self.inner,
+            sources,
+            target,
+            max_hops,
+        )
+    }
+
+    /// Same as [`WotGraph::multi_source_dump_wot`], but returns
+    /// [`error::Error::Cancelled`] once the token is cancelled.
+    #[inline(always)]
+    pub fn multi_source_dump_wot_cancellable(
+        &self,
+        sources: &[NodeIndex],
+        target: NodeIndex,
+        max_hops: u8,
+        cancel: &progress::CancellationToken,
+    ) -> Result<Vec<isize>, error::Error> {
+        traits::ms_bfs::MultiSourceBfsExt::multi_source_dump_wot_cancellable(
+            &self.inner,
+            sources,
+            target,
+            max_hops,
+            cancel,
+        )
+    }
+
+    /// Estimates, for every node, how many nodes are within `max_hops` from
+    /// it following the `Follow` edges, the node itself included.
+    ///
+    /// This is an approximation using HyperLogLog counters (HyperANF), much
+    /// faster than exact traversals on large graphs. The higher the
+    /// `precision` the more accurate and memory hungry the estimation is,
+    /// see [`traits::hyper_anf::HyperAnfExt::approx_neighborhood_sizes`].
+    ///
+    /// The returned vector is indexed by [`NodeIndex::index`].
+    ///
+    /// ```rust
+    /// use nostr_wot::{WotGraph, relations::Relation};
+    ///
+    /// let mut graph = WotGraph::new();
+    /// let node1 = graph.add_node(1).unwrap();
+    /// let node2 = graph.add_node(2).unwrap();
+    /// let node3 = graph.add_node(3).unwrap();
+    /// graph.add_edge(node1, node2, Relation::Follow).unwrap();
+    /// graph.add_edge(node2, node3, Relation::Follow).unwrap();
+    ///
+    /// let sizes = graph.approx_neighborhood_sizes(2, 10);
+    /// assert_eq!(sizes[node1.index()].round(), 3.0);
+    /// assert_eq!(sizes[node3.index()].round(), 1.0);
+    /// ```
+    #[inline(always)]
+    pub fn approx_neighborhood_sizes(&self, max_hops: u8, precision: u8) -> Vec<f64> {
+        traits::hyper_anf::HyperAnfExt::approx_neighborhood_sizes(&self.inner, max_hops, precision)
+    }
+
+    /// Same as [`WotGraph::approx_neighborhood_sizes`], but returns
+    /// [`error::Error::Cancelled`] once the token is cancelled.
+    #[inline(always)]
+    pub fn approx_neighborhood_sizes_cancellable(
+        &self,
+        max_hops: u8,
+        precision: u8,
+        cancel: &progress::CancellationToken,
+    ) -> Result<Vec<f64>, error::Error> {
+        traits::hyper_anf::HyperAnfExt::approx_neighborhood_sizes_cancellable(
+            &self.inner,
+            max_hops,
+            precision,
+            cancel,
+        )
+    }
 }
@@ -0,0 +1,285 @@
+// Copyright (c) 2026, Awiteb <a@4rs.nl>
+//     lightweight nostr Web of Trust library
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+#[cfg(all(
+    feature = "mmap",
+    not(any(target_arch = "wasm32", target_arch = "wasm64"))
+))]
+use std::{fs::File, path::Path};
+
+use byteorder::{ByteOrder, LittleEndian};
+use nostr::key::PublicKey;
+use petgraph::{Direction, graph::NodeIndex};
+
+use crate::{
+    error::GraphSerializationError,
+    relations::Relation,
+    traits::{
+        basic::{self, Adjacency},
+        dump_wot,
+    },
+    utils,
+};
+
+/// Magic bytes of the mapped layout.
+pub(crate) const MAGIC: [u8; 8] = *b"NWOTCSR\0";
+/// Version of the mapped layout.
+pub(crate) const VERSION: u32 = 1;
+/// Size of the mapped layout header.
+pub(crate) const HEADER_SIZE: usize = 64;
+/// Alignment of each section of the mapped layout.
+pub(crate) const ALIGNMENT: usize = 8;
+
+/// Rounds `pos` up to the sections alignment, `None` on overflow.
+#[inline]
+pub(crate) const fn align(pos: usize) -> Option<usize> {
+    pos.checked_next_multiple_of(ALIGNMENT)
+}
+
+/// Byte positions of a CSR adjacency in the mapped layout.
+#[derive(Debug, Clone, Copy, Default)]
+struct MappedCsr {
+    /// Position of the `node_count + 1` offsets.
+    offsets:   usize,
+    /// Position of the neighbors.
+    neighbors: usize,
+    /// Number of neighbors.
+    len:       usize,
+}
+
+/// A [`FrozenWotGraph`] queried directly from its exported bytes, without
+/// deserialization.
+///
+/// The bytes are the output of [`FrozenWotGraph::export_mapped`], usually a
+/// memory mapped file (see `MappedWotGraph::open` with the `mmap` feature),
+/// so several processes can share the same graph through the page cache.
+///
+/// Layout (little-endian, each section aligned to 8 bytes):
+/// - 8 bytes: magic `NWOTCSR\0`
+/// - 4 bytes: layout version
+/// - 4 bytes: reserved
+/// - 8 bytes: number of nodes (N)
+/// - 4 * 8 bytes: number of neighbors of each adjacency
+/// - 8 bytes: reserved
+/// - N * 8 bytes: node weights
+/// - for `Follow` then `Mute`, `Outgoing` then `Incoming`:
+///   - (N + 1) * 4 bytes: offsets
+///   - E * 4 bytes: neighbors
+///
+/// [`FrozenWotGraph`]: crate::frozen::FrozenWotGraph
+/// [`FrozenWotGraph::export_mapped`]: crate::frozen::FrozenWotGraph::export_mapped
+#[derive(Debug)]
+pub struct MappedWotGraph<B> {
+    /// The exported graph bytes.
+    bytes:      B,
+    /// Number of nodes in the graph.
+    node_count: usize,
+    /// The adjacency of each relation and direction, indexed by
+    /// `[relation][direction]`.
+    adjacency:  [[MappedCsr; 2]; 2],
+}
+
+impl<B: AsRef<[u8]>> MappedWotGraph<B> {
+    /// Validates the layout header and sections sizes, then wraps the bytes.
+    /// The bytes are not copied.
+    pub fn from_bytes(bytes: B) -> Result<Self, crate::error::Error> {
+        let data = bytes.as_ref();
+        if data.len() < HEADER_SIZE {
+            return Err(GraphSerializationError::InsufficientData(HEADER_SIZE).into());
+        }
+        if data[..8] != MAGIC {
+            return Err(GraphSerializationError::NotAWotGraph.into());
+        }
+        let version = LittleEndian::read_u32(&data[8..12]);
+        if version != VERSION {
+            return Err(GraphSerializationError::UnsupportedVersion(version).into());
+        }
+
+        // The sizes are untrusted, an overflow means the data can't be long
+        // enough
+        let too_large = || GraphSerializationError::InsufficientData(usize::MAX);
+        let read_size = |pos: usize| {
+            usize::try_from(LittleEndian::read_u64(&data[pos..pos + 8])).map_err(|_| too_large())
+        };
+
+        let node_count = read_size(16)?;
+        let offsets_size = node_count
+            .checked_add(1)
+            .and_then(|offsets| offsets.checked_mul(4))
+            .ok_or_else(too_large)?;
+        let mut pos = node_count
+            .checked_mul(8)
+            .and_then(|nodes| nodes.checked_add(HEADER_SIZE))
+            .ok_or_else(too_large)?;
+        let mut adjacency = [[MappedCsr::default(); 2]; 2];
+        for (idx, csr) in adjacency.iter_mut().flatten().enumerate() {
+            let len = read_size(24 + idx * 8)?;
+            let offsets = align(pos).ok_or_else(too_large)?;
+            let neighbors = offsets
+                .checked_add(offsets_size)
+                .and_then(align)
+                .ok_or_else(too_large)?;
+            pos = len
+                .checked_mul(4)
+                .and_then(|neighbors_size| neighbors.checked_add(neighbors_size))
+                .ok_or_else(too_large)?;
+            *csr = MappedCsr {
+                offsets,
+                neighbors,
+                len,
+            };
+        }
+
+        if data.len() < pos {
+            return Err(GraphSerializationError::InsufficientData(pos).into());
+        }
+
+        Ok(Self {
+            bytes,
+            node_count,
+            adjacency,
+        })
+    }
+
+    /// The underlying bytes.
+    #[inline]
+    pub fn bytes(&self) -> &B {
+        &self.bytes
+    }
+
+    /// Number of nodes in the graph.
+    #[inline]
+    pub fn node_count(&self) -> usize {
+        self.node_count
+    }
+
+    /// Number of edges in the graph.
+    #[inline]
+    pub fn edge_count(&self) -> usize {
+        self.adjacency
+            .iter()
+            .map(|directions| directions[0].len)
+            .sum()
+    }
+
+    /// Returns the node weight (public key hash), or `None` if it doesn't
+    /// exist.
+    #[inline]
+    pub fn node_weight(&self, node: NodeIndex) -> Option<u64> {
+        (node.index() < self.node_count)
+            .then(|| LittleEndian::read_u64(&self.bytes.as_ref()[HEADER_SIZE + node.index() * 8..]))
+    }
+
+    /// Returns the node index by public key. Returns `None` if the there is no
+    /// match
+    pub fn node_index(&self, pkey: &PublicKey) -> Option<NodeIndex> {
+        let pkey_hash = utils::hash_bytes(pkey.as_bytes());
+        self.bytes.as_ref()[HEADER_SIZE..HEADER_SIZE + self.node_count * 8]
+            .chunks_exact(8)
+            .position(|node| LittleEndian::read_u64(node) == pkey_hash)
+            .map(NodeIndex::new)
+    }
+
+    /// Finds the neighboring nodes of `source` based on the given `relation`
+    /// and `direction`. Same as
+    /// [`FrozenWotGraph::neighbors`](crate::frozen::FrozenWotGraph::neighbors).
+    ///
+    /// Out of bounds offsets and neighbors of a corrupted file are ignored.
+    pub fn neighbors(
+        &self,
+        source: NodeIndex,
+        relation: Relation,
+        direction: Direction,
+    ) -> impl Iterator<Item = NodeIndex> {
+        let data = self.bytes.as_ref();
+        let csr = self.adjacency[relation as usize][direction.index()];
+        let range = if source.index() < self.node_count {
+            let offset = csr.offsets + source.index() * 4;
+            let end = (LittleEndian::read_u32(&data[offset + 4..]) as usize).min(csr.len);
+            let start = (LittleEndian::read_u32(&data[offset..]) as usize).min(end);
+            csr.neighbors + start * 4..csr.neighbors + end * 4
+        } else {
+            0..0
+        };
+
+        data[range]
+            .chunks_exact(4)
+            .map(|idx| LittleEndian::read_u32(idx) as usize)
+            .filter(|idx| *idx < self.node_count)
+            .map(NodeIndex::new)
+    }
+
+    /// Counts how many nodes in the source's following hops (up to `max_hops`)
+    /// have the given `relation` with the target. Same as
+    /// [`WotGraph::count_neighbors_in_hops`](crate::WotGraph::count_neighbors_in_hops).
+    #[inline]
+    pub fn count_neighbors_in_hops(
+        &self,
+        source: NodeIndex,
+        target: NodeIndex,
+        relation: Relation,
+        max_hops: u8,
+    ) -> usize
+    where
+        B: Sync,
+    {
+        basic::count_matches_in_hops(self, source, target, relation, max_hops)
+    }
+
+    /// Counts the trust score between source and target within max_hops
+    /// distance. Same as [`WotGraph::dump_wot`](crate::WotGraph::dump_wot).
+    #[inline]
+    pub fn dump_wot(&self, source: NodeIndex, target: NodeIndex, max_hops: u8) -> isize
+    where
+        B: Sync,
+    {
+        dump_wot::score(
+            self.count_neighbors_in_hops(source, target, Relation::Follow, max_hops),
+            self.count_neighbors_in_hops(source, target, Relation::Mute, max_hops),
+        )
+    }
+}
+
+#[cfg(all(
+    feature = "mmap",
+    not(any(target_arch = "wasm32", target_arch = "wasm64"))
+))]
+impl MappedWotGraph<memmap2::Mmap> {
+    /// Memory maps a file exported using
+    /// [`FrozenWotGraph::export_to_file_mapped`](crate::frozen::FrozenWotGraph::export_to_file_mapped).
+    ///
+    /// The file must not be modified while it's mapped, the graph may see the
+    /// changes or fail to read them.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, crate::error::Error> {
+        let file = File::open(path)?;
+        // SAFETY: The mapping is read only and the bytes are validated before
+        // any access, modifying the file while mapped is documented above.
+        let mmap = unsafe { memmap2::Mmap::map(&file)? };
+        Self::from_bytes(mmap)
+    }
+}
+
+impl<B: AsRef<[u8]> + Sync> Adjacency for MappedWotGraph<B> {
+    #[inline]
+    fn node_count(&self) -> usize {
+        self.node_count
+    }
+
+    #[inline]
+    fn relation_neighbors(
+        &self,
+        node: NodeIndex,
+        relation: Relation,
+        direction: Direction,
+    ) -> impl Iterator<Item = NodeIndex> {
+        self.neighbors(node, relation, direction)
+    }
+}
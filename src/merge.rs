@@ -0,0 +1,83 @@
+// Copyright (c) 2026, Awiteb <a@4rs.nl>
+//     lightweight nostr Web of Trust library
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{WotGraph, relations::Relation, utils};
+
+/// How a list in both graphs is merged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Resolution {
+    /// Union the lists.
+    Union,
+    /// Keep the graph list.
+    Keep,
+    /// Replace the graph list with the other one.
+    Replace,
+}
+
+/// Merges `other` into the graph. The lists, the edges of an author and a
+/// relation, of every author in either graph are merged based on `resolve`,
+/// an author without edges of a relation has an empty list.
+pub(crate) fn merge<F: Fn(u64, Relation) -> Resolution>(
+    graph: &mut WotGraph,
+    other: &WotGraph,
+    resolve: F,
+) {
+    let other_graph = &other.inner;
+    let inner = graph.inner_mut();
+
+    // The lists not unioned, by author hash and relation
+    let authors: HashSet<u64> = inner
+        .raw_nodes()
+        .iter()
+        .chain(other_graph.raw_nodes())
+        .map(|node| node.weight)
+        .collect();
+    let mut resolutions = HashMap::new();
+    for author in authors {
+        for relation in [Relation::Follow, Relation::Mute] {
+            let resolution = resolve(author, relation);
+            if resolution != Resolution::Union {
+                resolutions.insert((author, relation as u8), resolution);
+            }
+        }
+    }
+
+    // The replaced lists of the graph
+    utils::remove_edges(inner, |source, relation, _| {
+        resolutions.get(&(source, relation)) == Some(&Resolution::Replace)
+    });
+
+    let mut nodes = utils::node_map(inner);
+    for node in other_graph.raw_nodes() {
+        nodes
+            .entry(node.weight)
+            .or_insert_with(|| inner.add_node(node.weight));
+    }
+
+    let mut edges: HashSet<(u64, u8, u64)> = inner
+        .raw_edges()
+        .iter()
+        .map(|edge| (inner[edge.source()], edge.weight, inner[edge.target()]))
+        .collect();
+    for edge in other_graph.raw_edges() {
+        let (author, relation) = (other_graph[edge.source()], edge.weight);
+        if resolutions.get(&(author, relation)) == Some(&Resolution::Keep) {
+            continue;
+        }
+
+        let target = other_graph[edge.target()];
+        if edges.insert((author, relation, target)) {
+            inner.add_edge(nodes[&author], nodes[&target], relation);
+        }
+    }
+}
@@ -0,0 +1,339 @@
+// Copyright (c) 2026, Awiteb <a@4rs.nl>
+//     lightweight nostr Web of Trust library
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use petgraph::graph::NodeIndex;
+
+use crate::{
+    error::{Error, Limit},
+    progress::{CancellationToken, Progress, ProgressReporter},
+};
+
+/// The encoding of the exported graph edges.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Encoding {
+    /// Each edge is stored as its source and target hashes (17 bytes per
+    /// edge). The edges order is preserved. Format version 1.
+    #[default]
+    Standard,
+    /// The edges are grouped by source and relation, and stored as varint
+    /// delta-encoded node indices. Several times smaller and faster to import,
+    /// but the edges are sorted by source, relation and target. Format
+    /// version 2.
+    Compact,
+}
+
+/// The compression codec of the exported graph. All the codecs are pure Rust.
+///
+/// The variants depend on the enabled features, so the enum is non-exhaustive.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Codec {
+    /// Gzip, levels from 0 (no compression) to 9 (best).
+    #[default]
+    Gzip,
+    /// LZ4 frame, the fastest to load. It has no compression levels, only
+    /// level 0 is accepted.
+    #[cfg(feature = "lz4")]
+    Lz4,
+    /// Zstandard. The pure Rust encoder only implements level 0 (no
+    /// compression) and level 1 (fastest). It has no streaming encoder, so
+    /// the whole export is buffered in memory before compressing it.
+    #[cfg(feature = "zstd")]
+    Zstd,
+}
+
+impl Codec {
+    /// The compression level used when the options don't set one.
+    #[inline]
+    pub const fn default_level(self) -> u32 {
+        match self {
+            Self::Gzip => 4,
+            #[cfg(feature = "lz4")]
+            Self::Lz4 => 0,
+            #[cfg(feature = "zstd")]
+            Self::Zstd => 1,
+        }
+    }
+
+    /// The highest compression level supported by the codec.
+    #[inline]
+    pub const fn max_level(self) -> u32 {
+        match self {
+            Self::Gzip => 9,
+            #[cfg(feature = "lz4")]
+            Self::Lz4 => 0,
+            #[cfg(feature = "zstd")]
+            Self::Zstd => 1,
+        }
+    }
+
+    /// Returns the given compression level, or the default one. Returns
+    /// [`Error::InvalidCompressionLevel`] if the codec doesn't support it.
+    pub(crate) fn level(self, level: Option<u32>) -> Result<u32, Error> {
+        let level = level.unwrap_or(self.default_level());
+        if level > self.max_level() {
+            return Err(Error::InvalidCompressionLevel(self, level));
+        }
+        Ok(level)
+    }
+}
+
+/// Options of the graph export.
+#[derive(Debug, Clone, Default)]
+pub struct ExportOptions {
+    /// The edges encoding.
+    pub encoding: Encoding,
+    /// The compression codec of the compressed exports.
+    pub codec:    Codec,
+    /// The compression level, `None` for [`Codec::default_level`]. The export
+    /// fails with [`Error::InvalidCompressionLevel`] above
+    /// [`Codec::max_level`].
+    pub level:    Option<u32>,
+    /// Reports the export progress.
+    pub progress: Option<ProgressReporter>,
+    /// Cancels the export, the written data is incomplete.
+    pub cancel:   Option<CancellationToken>,
+}
+
+impl ExportOptions {
+    /// Sets the edges encoding.
+    #[inline]
+    pub fn encoding(mut self, encoding: Encoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Sets the compression codec.
+    #[inline]
+    pub fn codec(mut self, codec: Codec) -> Self {
+        self.codec = codec;
+        self
+    }
+
+    /// Sets the compression level.
+    #[inline]
+    pub fn level(mut self, level: u32) -> Self {
+        self.level = Some(level);
+        self
+    }
+
+    /// Sets the progress reporter.
+    #[inline]
+    pub fn progress<F: Fn(Progress) + Send + Sync + 'static>(mut self, report: F) -> Self {
+        self.progress = Some(ProgressReporter::new(report));
+        self
+    }
+
+    /// Sets the cancellation token.
+    #[inline]
+    pub fn cancel(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+
+    /// The compression level of the codec.
+    #[inline]
+    pub(crate) fn compression_level(&self) -> Result<u32, Error> {
+        self.codec.level(self.level)
+    }
+}
+
+/// How the stored capacities of an imported graph are used. The capacities
+/// are untrusted, a small hostile file can claim billions of nodes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CapacityPolicy {
+    /// Preallocate the stored capacities as is. Only for trusted data.
+    Trust,
+    /// Preallocate the stored capacities, bounded by the import limits and
+    /// [`MAX_PREALLOCATION`]. The graph grows past it as the data is read.
+    #[default]
+    Clamp,
+    /// Don't preallocate, the graph grows as the data is read.
+    Ignore,
+}
+
+/// What to do with nodes of the same public key hash. [`WotGraph::add_node`]
+/// allows them, so the exported graphs may contain them.
+///
+/// [`WotGraph::add_node`]: crate::WotGraph::add_node
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DuplicatePolicy {
+    /// Keep the first node, the edges of the duplicates are attached to it.
+    #[default]
+    Merge,
+    /// Fail with [`GraphSerializationError::DuplicateNode`].
+    ///
+    /// [`GraphSerializationError::DuplicateNode`]: crate::error::GraphSerializationError::DuplicateNode
+    Error,
+}
+
+/// Maximum number of nodes or edges preallocated by
+/// [`CapacityPolicy::Clamp`].
+pub const MAX_PREALLOCATION: usize = 1 << 16;
+
+/// Options of the graph import.
+#[derive(Debug, Clone, Default)]
+pub struct ImportOptions {
+    /// Maximum number of nodes, `None` for no limit.
+    pub max_nodes:  Option<usize>,
+    /// Maximum number of edges, `None` for no limit.
+    pub max_edges:  Option<usize>,
+    /// How the stored capacities are used.
+    pub capacity:   CapacityPolicy,
+    /// What to do with duplicate nodes.
+    pub duplicates: DuplicatePolicy,
+    /// Reports the import progress.
+    pub progress:   Option<ProgressReporter>,
+    /// Cancels the import.
+    pub cancel:     Option<CancellationToken>,
+}
+
+impl ImportOptions {
+    /// Sets the maximum number of nodes.
+    #[inline]
+    pub fn max_nodes(mut self, max_nodes: usize) -> Self {
+        self.max_nodes = Some(max_nodes);
+        self
+    }
+
+    /// Sets the maximum number of edges.
+    #[inline]
+    pub fn max_edges(mut self, max_edges: usize) -> Self {
+        self.max_edges = Some(max_edges);
+        self
+    }
+
+    /// Sets the capacity policy.
+    #[inline]
+    pub fn capacity(mut self, capacity: CapacityPolicy) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Sets the duplicate nodes policy.
+    #[inline]
+    pub fn duplicates(mut self, duplicates: DuplicatePolicy) -> Self {
+        self.duplicates = duplicates;
+        self
+    }
+
+    /// Sets the progress reporter.
+    #[inline]
+    pub fn progress<F: Fn(Progress) + Send + Sync + 'static>(mut self, report: F) -> Self {
+        self.progress = Some(ProgressReporter::new(report));
+        self
+    }
+
+    /// Sets the cancellation token.
+    #[inline]
+    pub fn cancel(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+
+    /// Checks the stored counts against the limits, and returns them.
+    pub(crate) fn check_counts(
+        &self,
+        num_nodes: u64,
+        num_edges: u64,
+    ) -> Result<(usize, usize), crate::error::GraphSerializationError> {
+        let check = |kind, count: u64, limit: Option<usize>| {
+            usize::try_from(count)
+                .ok()
+                .filter(|count| limit.is_none_or(|limit| *count <= limit))
+                .ok_or(crate::error::GraphSerializationError::LimitExceeded(
+                    kind,
+                    count,
+                    limit.unwrap_or(usize::MAX),
+                ))
+        };
+
+        Ok((
+            check(Limit::Nodes, num_nodes, self.max_nodes)?,
+            check(Limit::Edges, num_edges, self.max_edges)?,
+        ))
+    }
+
+    /// Checks the number of nodes or edges of the graph being imported
+    /// against its limit.
+    pub(crate) fn check_limit(
+        &self,
+        limit: Limit,
+        count: usize,
+    ) -> Result<(), crate::error::GraphSerializationError> {
+        let max = match limit {
+            Limit::Nodes => self.max_nodes,
+            Limit::Edges => self.max_edges,
+        };
+        match max {
+            Some(max) if count > max => {
+                Err(crate::error::GraphSerializationError::LimitExceeded(
+                    limit,
+                    count as u64,
+                    max,
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns the nodes and edges capacities to preallocate.
+    pub(crate) fn capacities(&self, nodes_capacity: u64, edges_capacity: u64) -> (usize, usize) {
+        let clamp = |capacity: u64, limit: Option<usize>| {
+            match self.capacity {
+                CapacityPolicy::Trust => usize::try_from(capacity).unwrap_or(usize::MAX),
+                CapacityPolicy::Clamp => {
+                    usize::try_from(capacity)
+                        .unwrap_or(usize::MAX)
+                        .min(limit.unwrap_or(usize::MAX))
+                        .min(MAX_PREALLOCATION)
+                }
+                CapacityPolicy::Ignore => 0,
+            }
+        };
+
+        (
+            clamp(nodes_capacity, self.max_nodes),
+            clamp(edges_capacity, self.max_edges),
+        )
+    }
+}
+
+/// The node score attributes of the GraphML and GEXF exports.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct AttributeOptions {
+    /// Adds the `page_rank` attribute with the damping factor and number of
+    /// iterations, see [`WotGraph::page_rank`].
+    ///
+    /// [`WotGraph::page_rank`]: crate::WotGraph::page_rank
+    pub page_rank: Option<(f64, usize)>,
+    /// Adds the `dump_wot` attribute, the score of each node from the source
+    /// within the maximum hops, see [`WotGraph::dump_wot`].
+    ///
+    /// [`WotGraph::dump_wot`]: crate::WotGraph::dump_wot
+    pub dump_wot:  Option<(NodeIndex, u8)>,
+}
+
+impl AttributeOptions {
+    /// Adds the PageRank of each node.
+    #[inline]
+    pub fn page_rank(mut self, damping_factor: f64, iterations: usize) -> Self {
+        self.page_rank = Some((damping_factor, iterations));
+        self
+    }
+
+    /// Adds the dump WoT score of each node from the source.
+    #[inline]
+    pub fn dump_wot(mut self, source: NodeIndex, max_hops: u8) -> Self {
+        self.dump_wot = Some((source, max_hops));
+        self
+    }
+}
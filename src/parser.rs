@@ -11,104 +11,1127 @@
 
 use std::{
     collections::HashMap,
-    io::{Cursor, Read, Write},
+    io::{BufRead, ErrorKind, Read, Write},
 };
 
-use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use byteorder::{ByteOrder, LittleEndian, ReadBytesExt, WriteBytesExt};
+use flate2::{Compression, bufread::GzDecoder, write::GzEncoder};
+use petgraph::graph::NodeIndex;
+use xxhash_rust::xxh64::Xxh64;
 
-use crate::error::GraphSerializationError;
+use crate::{
+    error::{GraphSerializationError, Limit},
+    options::{Codec, DuplicatePolicy, Encoding, ExportOptions, ImportOptions, MAX_PREALLOCATION},
+    patch::{GraphPatch, PatchEdge},
+    progress::Tracker,
+    relations::Relation,
+    utils,
+};
+
+/// Magic bytes of the exported graphs, since version 1.
+pub const MAGIC: [u8; 4] = *b"NWOT";
+/// Format version of the [`Encoding::Standard`] encoding.
+pub const VERSION: u32 = 1;
+/// Format version of the [`Encoding::Compact`] encoding.
+pub const COMPACT_VERSION: u32 = 2;
+/// Magic bytes of the exported patches.
+pub const PATCH_MAGIC: [u8; 4] = *b"NWPT";
+/// Format version of the exported patches.
+pub const PATCH_VERSION: u32 = 1;
+/// Magic bytes of a gzip stream.
+pub(crate) const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+/// Magic bytes of a LZ4 frame.
+pub(crate) const LZ4_MAGIC: [u8; 4] = [0x04, 0x22, 0x4d, 0x18];
+/// Magic bytes of a Zstandard frame.
+pub(crate) const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+/// The size of the encoded chunks and of the decoded buffer.
+pub(crate) const CHUNK_SIZE: usize = 64 * 1024;
+
+/// A writer computing the xxh64 checksum of the written bytes.
+struct ChecksumWriter<W> {
+    inner:  W,
+    hasher: Xxh64,
+}
+
+impl<W: Write> ChecksumWriter<W> {
+    fn new(inner: W) -> Self {
+        Self {
+            inner,
+            hasher: Xxh64::new(utils::HASH_SEED),
+        }
+    }
+
+    /// Writes the checksum of the written bytes.
+    fn finish(mut self) -> Result<(), crate::error::Error> {
+        self.inner.write_u64::<LittleEndian>(self.hasher.digest())?;
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for ChecksumWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// A reader computing the xxh64 checksum of the read bytes.
+struct ChecksumReader<R> {
+    inner:  R,
+    hasher: Xxh64,
+}
+
+impl<R: Read> ChecksumReader<R> {
+    /// Creates the reader, `prefix` is the already read bytes.
+    fn new(inner: R, prefix: &[u8]) -> Self {
+        let mut hasher = Xxh64::new(utils::HASH_SEED);
+        hasher.update(prefix);
+        Self { inner, hasher }
+    }
+
+    /// Reads the stored checksum and compares it with the read bytes one.
+    fn verify(mut self, expected_size: usize) -> Result<(), crate::error::Error> {
+        let stored = self
+            .inner
+            .read_u64::<LittleEndian>()
+            .map_err(|_| GraphSerializationError::InsufficientData(expected_size))?;
+        let computed = self.hasher.digest();
+        if stored != computed {
+            return Err(GraphSerializationError::ChecksumMismatch(stored, computed).into());
+        }
+
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for ChecksumReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.hasher.update(&buf[..read]);
+        Ok(read)
+    }
+}
+
+/// Appends an unsigned LEB128 varint.
+fn put_varint(buf: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buf.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+/// Decodes an unsigned LEB128 varint from the start of the data. Returns the
+/// value and its length, or `None` if the data ends before the varint does.
+fn decode_varint(data: &[u8]) -> Result<Option<(u64, usize)>, GraphSerializationError> {
+    let mut value = 0u64;
+    for (idx, byte) in data.iter().take(10).enumerate() {
+        let shift = idx * 7;
+        // The 10th byte carries the last bit of the value
+        if shift == 63 && *byte > 1 {
+            return Err(GraphSerializationError::InvalidFormat);
+        }
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(Some((value, idx + 1)));
+        }
+    }
+    Ok(None)
+}
+
+/// Writes an unsigned LEB128 varint.
+fn write_varint<W: Write>(writer: &mut W, value: u64) -> Result<(), crate::error::Error> {
+    let mut buf = Vec::with_capacity(10);
+    put_varint(&mut buf, value);
+    writer.write_all(&buf)?;
+    Ok(())
+}
+
+/// Reads an unsigned LEB128 varint.
+fn read_varint<R: Read>(reader: &mut R, expected_size: usize) -> Result<u64, crate::error::Error> {
+    let mut buf = [0u8; 10];
+    for len in 1..=buf.len() {
+        buf[len - 1] = reader
+            .read_u8()
+            .map_err(|_| GraphSerializationError::InsufficientData(expected_size))?;
+        if let Some((value, _)) = decode_varint(&buf[..len])? {
+            return Ok(value);
+        }
+    }
+    Err(GraphSerializationError::InvalidFormat.into())
+}
 
 /// Export the graph nodes and edges in a binary format (little-endian)
 ///
 /// Format:
+/// - 4 bytes: magic `NWOT`
+/// - 4 bytes: format version
+/// - the body of the version, see [`GraphEncoder`]
+/// - 8 bytes: xxh64 checksum of all the previous bytes
+pub fn export_graph<W: Write>(
+    graph: &crate::GraphType,
+    writer: &mut W,
+    options: &ExportOptions,
+) -> Result<(), crate::error::Error> {
+    let mut encoder = GraphEncoder::new(graph, options)?;
+    let mut chunk = Vec::new();
+    while encoder.next_chunk(&mut chunk)? {
+        writer.write_all(&chunk)?;
+    }
+    Ok(())
+}
+
+/// Export the graph compressed with the options codec, see [`export_graph`].
+pub fn export_compressed<W: Write>(
+    graph: &crate::GraphType,
+    writer: &mut W,
+    options: &ExportOptions,
+) -> Result<(), crate::error::Error> {
+    let level = options.compression_level()?;
+    match options.codec {
+        Codec::Gzip => {
+            let mut encoder = GzEncoder::new(writer, Compression::new(level));
+            export_graph(graph, &mut encoder, options)?;
+            encoder.finish()?;
+        }
+        #[cfg(feature = "lz4")]
+        Codec::Lz4 => {
+            let mut encoder = lz4_flex::frame::FrameEncoder::new(writer);
+            export_graph(graph, &mut encoder, options)?;
+            encoder.finish().map_err(std::io::Error::from)?;
+        }
+        #[cfg(feature = "zstd")]
+        Codec::Zstd => {
+            use ruzstd::encoding::{CompressionLevel, compress_to_vec};
+
+            // The encoder panics on write errors, so it writes to memory
+            let mut exported_graph = Vec::new();
+            export_graph(graph, &mut exported_graph, options)?;
+            let level = match level {
+                0 => CompressionLevel::Uncompressed,
+                _ => CompressionLevel::Fastest,
+            };
+            writer.write_all(&compress_to_vec(exported_graph.as_slice(), level))?;
+        }
+    }
+    Ok(())
+}
+
+/// The header of the exported graph: the nodes and edges capacities, then the
+/// number of nodes and edges.
+fn header(graph: &crate::GraphType) -> [u64; 4] {
+    let (nodes_capacity, edges_capacity) = graph.capacity();
+    [
+        nodes_capacity as u64,
+        edges_capacity as u64,
+        graph.node_count() as u64,
+        graph.edge_count() as u64,
+    ]
+}
+
+/// The graph edges as `(source, relation, target)` indices, sorted for the
+/// compact encoding.
+fn sorted_edges(graph: &crate::GraphType) -> Result<Vec<(u32, u8, u32)>, crate::error::Error> {
+    let mut edges = Vec::new();
+    edges.try_reserve_exact(graph.edge_count())?;
+    edges.extend(graph.raw_edges().iter().map(|edge| {
+        (
+            edge.source().index() as u32,
+            edge.weight,
+            edge.target().index() as u32,
+        )
+    }));
+    edges.sort_unstable();
+    Ok(edges)
+}
+
+/// The encoder of the exported graph, shared by the sync and async parsers.
+/// It doesn't do any IO, the caller writes the encoded chunks.
+///
+/// Body format of version 0 and 1 ([`Encoding::Standard`]):
 /// - 8 bytes: nodes capacity
 /// - 8 bytes: edges capacity
 /// - 8 bytes: number of nodes
 /// - 8 bytes: number of edges
 /// - N * 8 bytes: node weights
 /// - E * 17 bytes: edges (8 bytes source, 1 byte relation, 8 bytes target)
-pub fn export_graph<W: Write>(
-    graph: &crate::GraphType,
-    writer: &mut W,
-) -> Result<(), crate::error::Error> {
-    let nodes = graph.raw_nodes();
-    let edges = graph.raw_edges();
-    let (nodes_capacity, edges_capacity) = graph.capacity();
+///
+/// Body format of version 2 ([`Encoding::Compact`]):
+/// - varint: nodes capacity
+/// - varint: edges capacity
+/// - varint: number of nodes
+/// - varint: number of edges
+/// - N * 8 bytes: node weights
+/// - edge groups, until all the edges are read:
+///   - varint: source index, delta from the previous group source
+///   - varint: number of relations
+///   - for each relation:
+///     - 1 byte: relation
+///     - varint: number of targets
+///     - varints: target indices, sorted and delta-encoded
+pub(crate) struct GraphEncoder<'a> {
+    /// The exported graph.
+    graph:    &'a crate::GraphType,
+    /// The edges encoding.
+    encoding: Encoding,
+    /// The sorted edges of the compact encoding.
+    edges:    Vec<(u32, u8, u32)>,
+    /// The export progress.
+    tracker:  Tracker<'a>,
+    /// The checksum of the encoded bytes.
+    hasher:   Xxh64,
+    /// The next part to encode.
+    state:    EncoderState,
+}
 
-    // Write header
-    writer.write_u64::<LittleEndian>(nodes_capacity as u64)?;
-    writer.write_u64::<LittleEndian>(edges_capacity as u64)?;
-    writer.write_u64::<LittleEndian>(nodes.len() as u64)?;
-    writer.write_u64::<LittleEndian>(edges.len() as u64)?;
+/// The next part of the graph to encode.
+#[derive(Clone, Copy)]
+enum EncoderState {
+    /// The magic bytes, the version and the header.
+    Header,
+    /// The node of the given index.
+    Node(usize),
+    /// The edge of the given index.
+    Edge(usize),
+    /// The checksum of all the previous bytes.
+    Checksum,
+    /// The whole graph is encoded.
+    Done,
+}
+
+impl<'a> GraphEncoder<'a> {
+    /// Creates the encoder of the graph with the options encoding.
+    pub(crate) fn new(
+        graph: &'a crate::GraphType,
+        options: &'a ExportOptions,
+    ) -> Result<Self, crate::error::Error> {
+        let tracker = Tracker::new(
+            graph.node_count(),
+            graph.edge_count(),
+            options.progress.as_ref(),
+            options.cancel.as_ref(),
+        )?;
+        let edges = match options.encoding {
+            Encoding::Standard => Vec::new(),
+            Encoding::Compact => sorted_edges(graph)?,
+        };
 
-    // Write nodes
-    for node in nodes {
-        writer.write_u64::<LittleEndian>(node.weight)?;
+        Ok(Self {
+            graph,
+            encoding: options.encoding,
+            edges,
+            tracker,
+            hasher: Xxh64::new(utils::HASH_SEED),
+            state: EncoderState::Header,
+        })
     }
 
-    // Write edges
-    for edge in edges {
-        writer.write_u64::<LittleEndian>(graph[edge.source()])?;
-        writer.write_u8(edge.weight)?;
-        writer.write_u64::<LittleEndian>(graph[edge.target()])?;
+    /// Encodes the next chunk of about [`CHUNK_SIZE`] bytes, replacing the
+    /// chunk content. Returns `false` once the whole graph is encoded.
+    pub(crate) fn next_chunk(&mut self, chunk: &mut Vec<u8>) -> Result<bool, crate::error::Error> {
+        chunk.clear();
+        while chunk.len() < CHUNK_SIZE {
+            match self.state {
+                EncoderState::Header => {
+                    self.encode_header(chunk);
+                    self.state = EncoderState::Node(0);
+                }
+                EncoderState::Node(idx) if idx < self.graph.node_count() => {
+                    chunk.extend(self.graph.raw_nodes()[idx].weight.to_le_bytes());
+                    self.tracker.node()?;
+                    self.state = EncoderState::Node(idx + 1);
+                }
+                EncoderState::Node(_) => self.state = EncoderState::Edge(0),
+                EncoderState::Edge(idx) if idx < self.graph.edge_count() => {
+                    self.encode_edge(idx, chunk);
+                    self.tracker.edge()?;
+                    self.state = EncoderState::Edge(idx + 1);
+                }
+                EncoderState::Edge(_) => self.state = EncoderState::Checksum,
+                EncoderState::Checksum | EncoderState::Done => break,
+            }
+        }
+
+        self.hasher.update(chunk);
+        if let EncoderState::Checksum = self.state {
+            chunk.extend(self.hasher.digest().to_le_bytes());
+            self.state = EncoderState::Done;
+        }
+        Ok(!chunk.is_empty())
     }
 
-    Ok(())
+    /// Encodes the magic bytes, the version and the header.
+    fn encode_header(&self, chunk: &mut Vec<u8>) {
+        chunk.extend(MAGIC);
+        match self.encoding {
+            Encoding::Standard => {
+                chunk.extend(VERSION.to_le_bytes());
+                for value in header(self.graph) {
+                    chunk.extend(value.to_le_bytes());
+                }
+            }
+            Encoding::Compact => {
+                chunk.extend(COMPACT_VERSION.to_le_bytes());
+                for value in header(self.graph) {
+                    put_varint(chunk, value);
+                }
+            }
+        }
+    }
+
+    /// Encodes the edge of the given index.
+    fn encode_edge(&self, idx: usize, chunk: &mut Vec<u8>) {
+        if let Encoding::Standard = self.encoding {
+            let edge = &self.graph.raw_edges()[idx];
+            chunk.extend(self.graph[edge.source()].to_le_bytes());
+            chunk.push(edge.weight);
+            chunk.extend(self.graph[edge.target()].to_le_bytes());
+            return;
+        }
+
+        // The first edge of a source or relation starts its group
+        let (source, relation, target) = self.edges[idx];
+        let previous = idx.checked_sub(1).map(|idx| self.edges[idx]);
+        let new_source = previous.is_none_or(|previous| previous.0 != source);
+        let new_relation = new_source || previous.is_some_and(|previous| previous.1 != relation);
+        let rest = &self.edges[idx..];
+        if new_source {
+            put_varint(
+                chunk,
+                u64::from(source - previous.map_or(0, |previous| previous.0)),
+            );
+            let source_edges = rest.partition_point(|edge| edge.0 == source);
+            let relations = rest[..source_edges].chunk_by(|a, b| a.1 == b.1).count();
+            put_varint(chunk, relations as u64);
+        }
+        if new_relation {
+            chunk.push(relation);
+            let targets = rest.partition_point(|edge| edge.0 == source && edge.1 == relation);
+            put_varint(chunk, targets as u64);
+        }
+
+        let previous_target = match previous {
+            Some(previous) if !new_relation => previous.2,
+            _ => 0,
+        };
+        put_varint(chunk, u64::from(target - previous_target));
+    }
 }
 
-/// Import the graph from binary format
-pub fn import_graph<R: Read>(mut data: R) -> Result<crate::GraphType, crate::error::Error> {
-    let mut header = [0u8; 32];
-    data.read_exact(&mut header)
-        .map_err(|_| GraphSerializationError::InsufficientData(32))?;
-    let mut header = Cursor::new(header);
-
-    // Read header
-    let nodes_capacity = header.read_u64::<LittleEndian>()? as usize;
-    let edges_capacity = header.read_u64::<LittleEndian>()? as usize;
-    let num_nodes = header.read_u64::<LittleEndian>()? as usize;
-    let num_edges = header.read_u64::<LittleEndian>()? as usize;
-
-    let expected_size = 32 + (num_nodes * 8) + (num_edges * 17);
-    // Create graph with appropriate capacity
-    // FIXME: Switch to `try_reserve` once it becomes available in petgraph.
-    // Pull Request: <https://github.com/petgraph/petgraph/pull/934>
-    let mut graph = crate::GraphType::with_capacity(nodes_capacity, edges_capacity);
-
-    // Build a map for fast node lookup
-    let mut node_map = HashMap::new();
-    node_map.try_reserve(num_nodes)?;
-
-    // Read nodes
-    for _ in 0..num_nodes {
-        let weight = data
-            .read_u64::<LittleEndian>()
-            .map_err(|_| GraphSerializationError::InsufficientData(expected_size))?;
-        let idx = graph.add_node(weight);
-        node_map.insert(weight, idx);
+/// Import the graph from binary format.
+///
+/// Graphs without the magic bytes are read as version 0, the format before
+/// versioning.
+pub fn import_graph<R: Read>(
+    mut data: R,
+    options: &ImportOptions,
+) -> Result<crate::GraphType, crate::error::Error> {
+    let mut decoder = GraphDecoder::new(options);
+    // The largest decoded part, the compact header, is way smaller
+    let mut buffer = vec![0; CHUNK_SIZE];
+    let mut len = 0;
+    while !decoder.is_done() {
+        let read = match data.read(&mut buffer[len..]) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err.into()),
+        };
+        len += read;
+        let decoded = decoder.decode(&buffer[..len])?;
+        buffer.copy_within(decoded..len, 0);
+        len -= decoded;
     }
+    decoder.finish()
+}
 
-    // Read edges
-    for _ in 0..num_edges {
-        let source_weight = data
-            .read_u64::<LittleEndian>()
-            .map_err(|_| GraphSerializationError::InsufficientData(expected_size))?;
-        let relation = data
-            .read_u8()
-            .map_err(|_| GraphSerializationError::InsufficientData(expected_size))?;
-        let target_weight = data
-            .read_u64::<LittleEndian>()
-            .map_err(|_| GraphSerializationError::InsufficientData(expected_size))?;
+/// Import the graph from binary format, compressed or not. The compression
+/// is detected from the magic bytes of the data, a stream of a codec disabled
+/// by the features is reported as [`GraphSerializationError::NotAWotGraph`].
+/// A headerless version 0 graph starting with the same bytes is read raw.
+pub fn import_graph_auto<R: BufRead>(
+    mut data: R,
+    options: &ImportOptions,
+) -> Result<crate::GraphType, crate::error::Error> {
+    let magic = data.fill_buf()?;
+    if is_v0_header(magic) {
+        return import_graph(data, options);
+    }
+    if magic.starts_with(&GZIP_MAGIC) {
+        return import_graph(GzDecoder::new(data), options);
+    }
+    #[cfg(feature = "lz4")]
+    if magic.starts_with(&LZ4_MAGIC) {
+        return import_graph(lz4_flex::frame::FrameDecoder::new(data), options);
+    }
+    #[cfg(feature = "zstd")]
+    if magic.starts_with(&ZSTD_MAGIC) {
+        let decoder = ruzstd::decoding::StreamingDecoder::new(data)
+            .map_err(|_| GraphSerializationError::InvalidFormat)?;
+        return import_graph(decoder, options);
+    }
+    import_graph(data, options)
+}
+
+/// Returns `true` if the data starts with a plausible headerless version 0
+/// graph header, its counts fit the node indices and don't exceed the
+/// capacities. Version 0 graphs may start with the magic bytes of a compressed
+/// stream, the header of a compressed stream doesn't pass this check.
+pub(crate) fn is_v0_header(data: &[u8]) -> bool {
+    if data.len() < 32 || data[..4] == MAGIC {
+        return false;
+    }
+
+    let [nodes_capacity, edges_capacity, num_nodes, num_edges] =
+        [0, 8, 16, 24].map(|offset| LittleEndian::read_u64(&data[offset..offset + 8]));
+    num_nodes <= nodes_capacity
+        && num_edges <= edges_capacity
+        && num_nodes <= u64::from(u32::MAX)
+        && num_edges <= u64::from(u32::MAX)
+}
+
+/// Maps the error of a failed version 0 import, data starting with the magic
+/// bytes of a compressed stream is reported as
+/// [`GraphSerializationError::NotAWotGraph`].
+fn v0_error(prefix: &[u8; 8], version: u32, err: crate::error::Error) -> crate::error::Error {
+    let compressed =
+        prefix[..2] == GZIP_MAGIC || prefix[..4] == LZ4_MAGIC || prefix[..4] == ZSTD_MAGIC;
+    if version == 0 && compressed {
+        GraphSerializationError::NotAWotGraph.into()
+    } else {
+        err
+    }
+}
+
+/// The graph being imported, shared by the importers of all the formats.
+pub(crate) struct GraphBuilder<'a> {
+    /// The imported graph.
+    graph:                    crate::GraphType,
+    /// The node of each public key hash, the first of the duplicates.
+    node_map:                 HashMap<u64, NodeIndex>,
+    /// The node of each stored index, duplicates are merged to the same node.
+    indices:                  Vec<NodeIndex>,
+    /// The import options.
+    options:                  &'a ImportOptions,
+    /// The import progress.
+    tracker:                  Tracker<'a>,
+    /// Number of nodes in the data.
+    pub(crate) num_nodes:     usize,
+    /// Number of edges in the data.
+    pub(crate) num_edges:     usize,
+    /// The minimum number of bytes of the data, including the magic bytes and
+    /// version.
+    pub(crate) expected_size: usize,
+}
+
+impl<'a> GraphBuilder<'a> {
+    /// Creates the graph from the stored header, the capacities then the
+    /// counts, of the given format version.
+    pub(crate) fn new(
+        [nodes_capacity, edges_capacity, num_nodes, num_edges]: [u64; 4],
+        version: u32,
+        options: &'a ImportOptions,
+    ) -> Result<Self, crate::error::Error> {
+        // Without magic bytes, the capacities are the only way to tell a graph
+        // from random data
+        if version == 0 && (nodes_capacity < num_nodes || edges_capacity < num_edges) {
+            return Err(GraphSerializationError::NotAWotGraph.into());
+        }
+
+        let (num_nodes, num_edges) = options.check_counts(num_nodes, num_edges)?;
+        let (nodes_capacity, edges_capacity) = options.capacities(nodes_capacity, edges_capacity);
+        // Saturates on overflow, such data can't be long enough anyway
+        let expected_size = if version == COMPACT_VERSION {
+            // Every varint takes at least one byte
+            num_nodes
+                .saturating_mul(8)
+                .saturating_add(num_edges)
+                .saturating_add(12)
+        } else {
+            let prefix_size = if version == 0 { 0 } else { 8 };
+            num_nodes
+                .saturating_mul(8)
+                .saturating_add(num_edges.saturating_mul(17))
+                .saturating_add(prefix_size + 32)
+        };
+
+        let mut graph = Self::with_capacity(
+            (nodes_capacity, edges_capacity),
+            num_nodes,
+            num_edges,
+            options,
+        )?;
+        graph.expected_size = expected_size;
+        Ok(graph)
+    }
+
+    /// Creates the graph of a format without header, from the counts of the
+    /// data. The counts are 0 if they are unknown until the end of the data,
+    /// the limits are then checked as the nodes and edges are added.
+    #[cfg(any(feature = "csv", feature = "json", feature = "serde"))]
+    pub(crate) fn with_counts(
+        num_nodes: usize,
+        num_edges: usize,
+        options: &'a ImportOptions,
+    ) -> Result<Self, crate::error::Error> {
+        let (num_nodes, num_edges) = options.check_counts(num_nodes as u64, num_edges as u64)?;
+        let capacities = options.capacities(num_nodes as u64, num_edges as u64);
+        Self::with_capacity(capacities, num_nodes, num_edges, options)
+    }
+
+    /// Creates the graph with the given capacities.
+    fn with_capacity(
+        (nodes_capacity, edges_capacity): (usize, usize),
+        num_nodes: usize,
+        num_edges: usize,
+        options: &'a ImportOptions,
+    ) -> Result<Self, crate::error::Error> {
+        // FIXME: Switch to `try_reserve` once it becomes available in petgraph.
+        // Pull Request: <https://github.com/petgraph/petgraph/pull/934>
+        let graph = crate::GraphType::with_capacity(nodes_capacity, edges_capacity);
+        let mut node_map = HashMap::new();
+        node_map.try_reserve(nodes_capacity)?;
+        let mut indices = Vec::new();
+        indices.try_reserve(nodes_capacity)?;
+        let tracker = Tracker::new(
+            num_nodes,
+            num_edges,
+            options.progress.as_ref(),
+            options.cancel.as_ref(),
+        )?;
+
+        Ok(Self {
+            graph,
+            node_map,
+            indices,
+            options,
+            tracker,
+            num_nodes,
+            num_edges,
+            expected_size: 0,
+        })
+    }
+
+    /// The error of data shorter than expected.
+    #[inline]
+    pub(crate) fn insufficient_data(&self) -> GraphSerializationError {
+        GraphSerializationError::InsufficientData(self.expected_size)
+    }
+
+    /// Adds the imported node to the graph, or merges it with its first
+    /// occurrence based on the duplicate policy.
+    pub(crate) fn add_node(&mut self, weight: u64) -> Result<(), crate::error::Error> {
+        let idx = match self.node_map.get(&weight) {
+            Some(idx) => {
+                match self.options.duplicates {
+                    DuplicatePolicy::Merge => *idx,
+                    DuplicatePolicy::Error => {
+                        return Err(GraphSerializationError::DuplicateNode(weight).into());
+                    }
+                }
+            }
+            None => self.push_node(weight)?,
+        };
+        self.indices.push(idx);
+        self.tracker.node()
+    }
+
+    /// Returns the node of the public key hash, adding it to the graph if
+    /// it's missing. For the formats without a list of nodes.
+    #[cfg(feature = "csv")]
+    pub(crate) fn node(&mut self, weight: u64) -> Result<NodeIndex, crate::error::Error> {
+        if let Some(idx) = self.node_map.get(&weight) {
+            return Ok(*idx);
+        }
+
+        let idx = self.push_node(weight)?;
+        self.tracker.node()?;
+        Ok(idx)
+    }
+
+    /// Adds a new node to the graph, checking the nodes limit.
+    fn push_node(&mut self, weight: u64) -> Result<NodeIndex, crate::error::Error> {
+        self.options
+            .check_limit(Limit::Nodes, self.graph.node_count() + 1)?;
+        let idx = self.graph.add_node(weight);
+        self.node_map.insert(weight, idx);
+        Ok(idx)
+    }
+
+    /// Adds an edge between the nodes of the given public key hashes.
+    pub(crate) fn add_edge(
+        &mut self,
+        source: u64,
+        relation: u8,
+        target: u64,
+    ) -> Result<(), crate::error::Error> {
+        let source_idx = *self
+            .node_map
+            .get(&source)
+            .ok_or(GraphSerializationError::NodeNotFound(source))?;
+        let target_idx = *self
+            .node_map
+            .get(&target)
+            .ok_or(GraphSerializationError::NodeNotFound(target))?;
+
+        self.push_edge(source_idx, relation, target_idx)
+    }
+
+    /// Checks the stored index and converts it to a node index.
+    pub(crate) fn node_index(&self, index: u64) -> Result<NodeIndex, GraphSerializationError> {
+        usize::try_from(index)
+            .ok()
+            .and_then(|index| self.indices.get(index).copied())
+            .ok_or(GraphSerializationError::NodeNotFound(index))
+    }
+
+    /// Adds an edge between the nodes of the given stored indices.
+    pub(crate) fn add_indexed_edge(
+        &mut self,
+        source: NodeIndex,
+        relation: u8,
+        target: u64,
+    ) -> Result<(), crate::error::Error> {
+        let target = self.node_index(target)?;
+        self.push_edge(source, relation, target)
+    }
+
+    /// Adds an edge between the nodes, checking the edges limit.
+    pub(crate) fn push_edge(
+        &mut self,
+        source: NodeIndex,
+        relation: u8,
+        target: NodeIndex,
+    ) -> Result<(), crate::error::Error> {
+        self.options
+            .check_limit(Limit::Edges, self.graph.edge_count() + 1)?;
+        self.graph.add_edge(source, target, relation);
+        self.tracker.edge()
+    }
+
+    /// Returns the imported graph, after reporting the final progress.
+    pub(crate) fn finish(mut self) -> Result<crate::GraphType, crate::error::Error> {
+        self.tracker.finish()?;
+        Ok(self.graph)
+    }
+}
+
+/// The decoder of the imported graph, shared by the sync and async parsers.
+/// It doesn't do any IO, the caller passes it the read data. See
+/// [`GraphEncoder`] for the format.
+pub(crate) struct GraphDecoder<'a> {
+    /// The import options.
+    options: &'a ImportOptions,
+    /// The first 8 bytes of the data.
+    prefix:  [u8; 8],
+    /// The format version, 0 for the data without the magic bytes.
+    version: u32,
+    /// The imported graph, once the header is decoded.
+    graph:   Option<GraphBuilder<'a>>,
+    /// The position in the compact edge groups.
+    compact: CompactEdges,
+    /// The checksum of the decoded bytes.
+    hasher:  Xxh64,
+    /// The next part to decode.
+    state:   DecoderState,
+}
+
+/// The position in the compact edge groups.
+#[derive(Default)]
+struct CompactEdges {
+    /// The edges left to decode.
+    remaining_edges: usize,
+    /// The stored index of the group source.
+    source:          u64,
+    /// The node of the group source.
+    source_idx:      NodeIndex,
+    /// The relations left in the source group.
+    relations:       u64,
+    /// The relation of the targets.
+    relation:        u8,
+    /// The targets left in the relation group.
+    targets:         usize,
+    /// The stored index of the previous target.
+    target:          u64,
+}
+
+impl CompactEdges {
+    /// The next part after a relation group.
+    fn next_group(&self) -> DecoderState {
+        if self.relations != 0 {
+            DecoderState::Relation
+        } else if self.remaining_edges != 0 {
+            DecoderState::Source
+        } else {
+            DecoderState::Checksum
+        }
+    }
+}
 
-        let source_idx = node_map
-            .get(&source_weight)
-            .ok_or(GraphSerializationError::NodeNotFound(source_weight))?;
-        let target_idx = node_map
-            .get(&target_weight)
-            .ok_or(GraphSerializationError::NodeNotFound(target_weight))?;
+/// The next part of the graph to decode.
+#[derive(Clone, Copy)]
+enum DecoderState {
+    /// The magic bytes and the version.
+    Prefix,
+    /// The capacities and the counts.
+    Header,
+    /// The number of nodes left.
+    Nodes(usize),
+    /// The number of edges left, of the standard encoding.
+    Edges(usize),
+    /// The source of a compact group.
+    Source,
+    /// The number of relations of a compact group.
+    Relations,
+    /// A relation and its number of targets.
+    Relation,
+    /// A target of the relation.
+    Target,
+    /// The checksum of all the previous bytes.
+    Checksum,
+    /// The whole graph is decoded.
+    Done,
+}
+
+impl<'a> GraphDecoder<'a> {
+    /// Creates the decoder with the import options.
+    pub(crate) fn new(options: &'a ImportOptions) -> Self {
+        Self {
+            options,
+            prefix: [0; 8],
+            version: 0,
+            graph: None,
+            compact: CompactEdges::default(),
+            hasher: Xxh64::new(utils::HASH_SEED),
+            state: DecoderState::Prefix,
+        }
+    }
 
-        graph.add_edge(*source_idx, *target_idx, relation);
+    /// Returns `true` once the whole graph is decoded.
+    #[inline]
+    pub(crate) fn is_done(&self) -> bool {
+        matches!(self.state, DecoderState::Done)
     }
 
-    Ok(graph)
+    /// Decodes the complete parts at the start of the data, and returns the
+    /// number of decoded bytes. The rest should be passed again, followed by
+    /// the next data.
+    pub(crate) fn decode(&mut self, data: &[u8]) -> Result<usize, crate::error::Error> {
+        self.decode_parts(data)
+            .map_err(|err| v0_error(&self.prefix, self.version, err))
+    }
+
+    /// Returns the imported graph, or the error of data ending before it.
+    pub(crate) fn finish(self) -> Result<crate::GraphType, crate::error::Error> {
+        let Some(graph) = self.graph else {
+            let header_size = if self.version == COMPACT_VERSION {
+                12
+            } else {
+                32
+            };
+            let err = GraphSerializationError::InsufficientData(header_size).into();
+            return Err(v0_error(&self.prefix, self.version, err));
+        };
+
+        let err = match self.state {
+            DecoderState::Done => return graph.finish(),
+            DecoderState::Checksum => {
+                GraphSerializationError::InsufficientData(graph.expected_size.saturating_add(8))
+            }
+            _ => graph.insufficient_data(),
+        };
+        Err(v0_error(&self.prefix, self.version, err.into()))
+    }
+
+    /// Decodes the parts then the checksum.
+    fn decode_parts(&mut self, data: &[u8]) -> Result<usize, crate::error::Error> {
+        let mut read = 0;
+        while let Some(len) = self.decode_part(&data[read..])? {
+            read += len;
+        }
+        if self.version != 0 {
+            self.hasher.update(&data[..read]);
+        }
+
+        if let (DecoderState::Checksum, Some(stored)) = (self.state, data[read..].first_chunk()) {
+            let stored = u64::from_le_bytes(*stored);
+            let computed = self.hasher.digest();
+            if stored != computed {
+                return Err(GraphSerializationError::ChecksumMismatch(stored, computed).into());
+            }
+            self.state = DecoderState::Done;
+            read += 8;
+        }
+        Ok(read)
+    }
+
+    /// Decodes the next part, returns its length or `None` if the data ends
+    /// before it or if it's the checksum.
+    fn decode_part(&mut self, data: &[u8]) -> Result<Option<usize>, crate::error::Error> {
+        if let DecoderState::Prefix | DecoderState::Header = self.state {
+            return self.decode_header(data);
+        }
+        let Some(graph) = &mut self.graph else {
+            return Ok(None);
+        };
+        let compact = &mut self.compact;
+
+        let (state, len) = match self.state {
+            DecoderState::Nodes(0) if self.version == COMPACT_VERSION => (compact.next_group(), 0),
+            DecoderState::Nodes(0) => (DecoderState::Edges(graph.num_edges), 0),
+            DecoderState::Nodes(left) => {
+                let Some(weight) = data.first_chunk() else {
+                    return Ok(None);
+                };
+                graph.add_node(u64::from_le_bytes(*weight))?;
+                (DecoderState::Nodes(left - 1), 8)
+            }
+            DecoderState::Edges(0) if self.version == 0 => (DecoderState::Done, 0),
+            DecoderState::Edges(0) => (DecoderState::Checksum, 0),
+            DecoderState::Edges(left) => {
+                let Some(edge) = data.first_chunk::<17>() else {
+                    return Ok(None);
+                };
+                graph.add_edge(
+                    LittleEndian::read_u64(&edge[..8]),
+                    edge[8],
+                    LittleEndian::read_u64(&edge[9..]),
+                )?;
+                (DecoderState::Edges(left - 1), 17)
+            }
+            DecoderState::Source => {
+                let Some((delta, len)) = decode_varint(data)? else {
+                    return Ok(None);
+                };
+                compact.source = compact
+                    .source
+                    .checked_add(delta)
+                    .ok_or(GraphSerializationError::InvalidFormat)?;
+                compact.source_idx = graph.node_index(compact.source)?;
+                (DecoderState::Relations, len)
+            }
+            DecoderState::Relations => {
+                let Some((relations, len)) = decode_varint(data)? else {
+                    return Ok(None);
+                };
+                compact.relations = relations;
+                (compact.next_group(), len)
+            }
+            DecoderState::Relation => {
+                let Some((&relation, rest)) = data.split_first() else {
+                    return Ok(None);
+                };
+                let Some((targets, len)) = decode_varint(rest)? else {
+                    return Ok(None);
+                };
+                let targets =
+                    usize::try_from(targets).map_err(|_| GraphSerializationError::InvalidFormat)?;
+                compact.remaining_edges = compact
+                    .remaining_edges
+                    .checked_sub(targets)
+                    .ok_or(GraphSerializationError::InvalidFormat)?;
+                compact.relations -= 1;
+                compact.relation = relation;
+                compact.targets = targets;
+                compact.target = 0;
+                if targets == 0 {
+                    (compact.next_group(), 1 + len)
+                } else {
+                    (DecoderState::Target, 1 + len)
+                }
+            }
+            DecoderState::Target => {
+                let Some((delta, len)) = decode_varint(data)? else {
+                    return Ok(None);
+                };
+                compact.target = compact
+                    .target
+                    .checked_add(delta)
+                    .ok_or(GraphSerializationError::InvalidFormat)?;
+                graph.add_indexed_edge(compact.source_idx, compact.relation, compact.target)?;
+                compact.targets -= 1;
+                if compact.targets == 0 {
+                    (compact.next_group(), len)
+                } else {
+                    (DecoderState::Target, len)
+                }
+            }
+            DecoderState::Prefix
+            | DecoderState::Header
+            | DecoderState::Checksum
+            | DecoderState::Done => return Ok(None),
+        };
+
+        self.state = state;
+        Ok(Some(len))
+    }
+
+    /// Decodes the magic bytes and the version, then the header.
+    fn decode_header(&mut self, data: &[u8]) -> Result<Option<usize>, crate::error::Error> {
+        if let DecoderState::Prefix = self.state {
+            let Some(prefix) = data.first_chunk() else {
+                return Ok(None);
+            };
+            self.prefix = *prefix;
+            self.state = DecoderState::Header;
+            if prefix[..4] != MAGIC {
+                return Ok(Some(0));
+            }
+
+            self.version = LittleEndian::read_u32(&prefix[4..]);
+            if !matches!(self.version, VERSION | COMPACT_VERSION) {
+                return Err(GraphSerializationError::UnsupportedVersion(self.version).into());
+            }
+            return Ok(Some(8));
+        }
+
+        let mut header = [0u64; 4];
+        let mut len = 0;
+        if self.version == COMPACT_VERSION {
+            for value in &mut header {
+                let Some((varint, varint_len)) = decode_varint(&data[len..])? else {
+                    return Ok(None);
+                };
+                *value = varint;
+                len += varint_len;
+            }
+        } else {
+            let Some(bytes) = data.get(..32) else {
+                return Ok(None);
+            };
+            LittleEndian::read_u64_into(bytes, &mut header);
+            len = 32;
+        }
+
+        let graph = GraphBuilder::new(header, self.version, self.options)?;
+        self.compact.remaining_edges = graph.num_edges;
+        self.state = DecoderState::Nodes(graph.num_nodes);
+        self.graph = Some(graph);
+        Ok(Some(len))
+    }
+}
+
+/// Export the patch in a binary format (little-endian)
+///
+/// Format:
+/// - 4 bytes: magic `NWPT`
+/// - 4 bytes: format version
+/// - varint: number of added nodes
+/// - varint: number of removed nodes
+/// - varint: number of added edges
+/// - varint: number of removed edges
+/// - 8 bytes each: added then removed node hashes
+/// - for the added then the removed edges, groups until all the edges are read:
+///   - 8 bytes: source hash
+///   - varint: number of edges
+///   - for each edge: 1 byte relation, 8 bytes target hash
+/// - 8 bytes: xxh64 checksum of all the previous bytes
+pub fn export_patch<W: Write>(
+    patch: &GraphPatch,
+    writer: &mut W,
+) -> Result<(), crate::error::Error> {
+    let mut writer = ChecksumWriter::new(writer);
+    writer.write_all(&PATCH_MAGIC)?;
+    writer.write_u32::<LittleEndian>(PATCH_VERSION)?;
+    for count in [
+        patch.added_nodes.len(),
+        patch.removed_nodes.len(),
+        patch.added_edges.len(),
+        patch.removed_edges.len(),
+    ] {
+        write_varint(&mut writer, count as u64)?;
+    }
+
+    for hash in patch.added_nodes.iter().chain(&patch.removed_nodes) {
+        writer.write_u64::<LittleEndian>(*hash)?;
+    }
+    for edges in [&patch.added_edges, &patch.removed_edges] {
+        for group in edges.chunk_by(|a, b| a.0 == b.0) {
+            writer.write_u64::<LittleEndian>(group[0].0)?;
+            write_varint(&mut writer, group.len() as u64)?;
+            for (_, relation, target) in group {
+                writer.write_u8(*relation as u8)?;
+                writer.write_u64::<LittleEndian>(*target)?;
+            }
+        }
+    }
+
+    writer.finish()
+}
+
+/// Import a patch exported using [`export_patch`].
+pub fn import_patch<R: Read>(mut data: R) -> Result<GraphPatch, crate::error::Error> {
+    let mut prefix = [0u8; 8];
+    data.read_exact(&mut prefix)
+        .map_err(|_| GraphSerializationError::InsufficientData(20))?;
+    if prefix[..4] != PATCH_MAGIC {
+        return Err(GraphSerializationError::NotAWotGraph.into());
+    }
+    let version = u32::from_le_bytes([prefix[4], prefix[5], prefix[6], prefix[7]]);
+    if version != PATCH_VERSION {
+        return Err(GraphSerializationError::UnsupportedVersion(version).into());
+    }
+
+    let mut data = ChecksumReader::new(data, &prefix);
+    let mut counts = [0u64; 4];
+    for count in &mut counts {
+        *count = read_varint(&mut data, 20)?;
+    }
+    // The smallest patch of these counts
+    let expected_size = (counts[0].saturating_add(counts[1]))
+        .saturating_mul(8)
+        .saturating_add(counts[2].saturating_add(counts[3]).saturating_mul(9))
+        .saturating_add(20);
+    let expected_size = usize::try_from(expected_size).unwrap_or(usize::MAX);
+    let insufficient_data = || GraphSerializationError::InsufficientData(expected_size);
+
+    let mut read_nodes = |count: u64| {
+        let mut nodes = Vec::with_capacity((count as usize).min(MAX_PREALLOCATION));
+        for _ in 0..count {
+            nodes.push(
+                data.read_u64::<LittleEndian>()
+                    .map_err(|_| insufficient_data())?,
+            );
+        }
+        Ok::<_, crate::error::Error>(nodes)
+    };
+    let added_nodes = read_nodes(counts[0])?;
+    let removed_nodes = read_nodes(counts[1])?;
+
+    let mut read_edges = |count: u64| {
+        let mut edges: Vec<PatchEdge> = Vec::with_capacity((count as usize).min(MAX_PREALLOCATION));
+        while (edges.len() as u64) < count {
+            let source = data
+                .read_u64::<LittleEndian>()
+                .map_err(|_| insufficient_data())?;
+            let group = read_varint(&mut data, expected_size)?;
+            if group == 0 || group > count - edges.len() as u64 {
+                return Err(GraphSerializationError::InvalidFormat.into());
+            }
+            for _ in 0..group {
+                let relation =
+                    Relation::try_from(data.read_u8().map_err(|_| insufficient_data())?)?;
+                let target = data
+                    .read_u64::<LittleEndian>()
+                    .map_err(|_| insufficient_data())?;
+                edges.push((source, relation, target));
+            }
+        }
+        Ok::<_, crate::error::Error>(edges)
+    };
+    let added_edges = read_edges(counts[2])?;
+    let removed_edges = read_edges(counts[3])?;
+
+    data.verify(expected_size)?;
+    Ok(GraphPatch {
+        added_nodes,
+        removed_nodes,
+        added_edges,
+        removed_edges,
+    })
 }
@@ -0,0 +1,160 @@
+// Copyright (c) 2026, Awiteb <a@4rs.nl>
+//     lightweight nostr Web of Trust library
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use std::collections::HashSet;
+
+use petgraph::{Direction, graph::NodeIndex, visit::EdgeRef};
+
+use crate::{WotGraph, error::GraphSerializationError, parser, relations::Relation, utils};
+
+/// An edge of a patch, `(source hash, relation, target hash)`.
+pub type PatchEdge = (u64, Relation, u64);
+
+/// The changes between two graph snapshots, see [`WotGraph::diff`] and
+/// [`WotGraph::apply_patch`].
+///
+/// The nodes are identified by their hash and the edges by their source hash,
+/// relation and target hash, so a patch applies to any snapshot with the same
+/// nodes and edges regardless of their indices. Duplicated nodes and edges
+/// are compared once.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GraphPatch {
+    /// The hashes of the added nodes, sorted.
+    pub added_nodes:   Vec<u64>,
+    /// The hashes of the removed nodes, sorted. Their edges are removed too.
+    pub removed_nodes: Vec<u64>,
+    /// The added edges, sorted.
+    pub added_edges:   Vec<PatchEdge>,
+    /// The removed edges, sorted.
+    pub removed_edges: Vec<PatchEdge>,
+}
+
+impl GraphPatch {
+    /// Returns `true` if the patch has no changes.
+    pub fn is_empty(&self) -> bool {
+        self.added_nodes.is_empty()
+            && self.removed_nodes.is_empty()
+            && self.added_edges.is_empty()
+            && self.removed_edges.is_empty()
+    }
+
+    /// Exports the patch in a binary format, with the edges grouped by source
+    /// and an xxh64 checksum.
+    pub fn export(&self) -> Result<Vec<u8>, crate::error::Error> {
+        let mut data = Vec::new();
+        parser::export_patch(self, &mut data)?;
+        Ok(data)
+    }
+
+    /// Imports a patch exported using [`GraphPatch::export`].
+    pub fn import(data: &[u8]) -> Result<Self, crate::error::Error> {
+        parser::import_patch(data)
+    }
+}
+
+/// The node hashes of the graph.
+fn node_set(graph: &crate::GraphType) -> HashSet<u64> {
+    graph.raw_nodes().iter().map(|node| node.weight).collect()
+}
+
+/// The edges of the graph by hashes. Edges with an invalid relation are
+/// skipped.
+fn edge_set(graph: &crate::GraphType) -> HashSet<PatchEdge> {
+    graph
+        .raw_edges()
+        .iter()
+        .filter_map(|edge| {
+            Some((
+                graph[edge.source()],
+                Relation::try_from(edge.weight).ok()?,
+                graph[edge.target()],
+            ))
+        })
+        .collect()
+}
+
+/// The sorted items of `left` missing from `right`.
+fn sorted_difference<T: Ord + Copy + std::hash::Hash>(
+    left: &HashSet<T>,
+    right: &HashSet<T>,
+) -> Vec<T> {
+    let mut items: Vec<T> = left.difference(right).copied().collect();
+    items.sort_unstable();
+    items
+}
+
+/// The patch turning `old` into `new`.
+pub(crate) fn diff(old: &crate::GraphType, new: &crate::GraphType) -> GraphPatch {
+    let (old_nodes, new_nodes) = (node_set(old), node_set(new));
+    let (old_edges, new_edges) = (edge_set(old), edge_set(new));
+
+    GraphPatch {
+        added_nodes:   sorted_difference(&new_nodes, &old_nodes),
+        removed_nodes: sorted_difference(&old_nodes, &new_nodes),
+        added_edges:   sorted_difference(&new_edges, &old_edges),
+        removed_edges: sorted_difference(&old_edges, &new_edges),
+    }
+}
+
+/// Applies the patch to the graph. The patch is validated first, the graph
+/// is unchanged if it doesn't apply. A full graph is only detected while
+/// adding the nodes and edges, the patch is then partially applied.
+pub(crate) fn apply(graph: &mut WotGraph, patch: &GraphPatch) -> Result<(), crate::error::Error> {
+    let removed_nodes: HashSet<u64> = patch.removed_nodes.iter().copied().collect();
+    let added_nodes: HashSet<u64> = patch.added_nodes.iter().copied().collect();
+    let mut nodes = utils::node_map(&graph.inner);
+    nodes.retain(|hash, _| !removed_nodes.contains(hash));
+    for (source, _, target) in &patch.added_edges {
+        for hash in [source, target] {
+            if !nodes.contains_key(hash) && !added_nodes.contains(hash) {
+                return Err(GraphSerializationError::NodeNotFound(*hash).into());
+            }
+        }
+    }
+
+    let inner = graph.inner_mut();
+
+    // The removed edges of every node with the same hashes
+    let removed_edges: HashSet<&PatchEdge> = patch.removed_edges.iter().collect();
+    utils::remove_edges(inner, |source, relation, target| {
+        Relation::try_from(relation)
+            .is_ok_and(|relation| removed_edges.contains(&(source, relation, target)))
+    });
+
+    // Removing the nodes from the last index only moves the nodes after the
+    // removed ones
+    let mut removed: Vec<NodeIndex> = inner
+        .node_indices()
+        .filter(|idx| removed_nodes.contains(&inner[*idx]))
+        .collect();
+    removed.sort_unstable_by(|a, b| b.cmp(a));
+    for idx in removed {
+        inner.remove_node(idx);
+    }
+
+    let mut nodes = utils::node_map(inner);
+    for hash in &patch.added_nodes {
+        if !nodes.contains_key(hash) {
+            nodes.insert(*hash, inner.try_add_node(*hash)?);
+        }
+    }
+    for (source, relation, target) in &patch.added_edges {
+        let (source, target) = (nodes[source], nodes[target]);
+        let exists = inner
+            .edges_directed(source, Direction::Outgoing)
+            .any(|edge| edge.target() == target && *edge.weight() == *relation as u8);
+        if !exists {
+            inner.try_add_edge(source, target, *relation as u8)?;
+        }
+    }
+
+    Ok(())
+}
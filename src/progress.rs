@@ -0,0 +1,178 @@
+// Copyright (c) 2026, Awiteb <a@4rs.nl>
+//     lightweight nostr Web of Trust library
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use std::{
+    fmt,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+/// Number of records between two progress reports or cancellation checks.
+pub const REPORT_INTERVAL: usize = 4096;
+
+/// A token to cancel a running import, export or algorithm from another
+/// thread or task. The clones share the same state.
+///
+/// ```rust
+/// use nostr_wot::progress::CancellationToken;
+///
+/// let token = CancellationToken::new();
+/// let clone = token.clone();
+/// clone.cancel();
+/// assert!(token.is_cancelled());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    /// Creates a new token, not cancelled.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancels the operations using the token.
+    #[inline]
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns `true` if the token is cancelled.
+    #[inline]
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Returns [`crate::error::Error::Cancelled`] if the token is cancelled.
+    #[inline]
+    pub(crate) fn check(&self) -> Result<(), crate::error::Error> {
+        if self.is_cancelled() {
+            return Err(crate::error::Error::Cancelled);
+        }
+        Ok(())
+    }
+}
+
+/// The progress of an import or export.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Progress {
+    /// Number of the processed nodes.
+    pub nodes:       usize,
+    /// Number of the graph nodes, from the stored header on import. The
+    /// number of the processed nodes for the streamed formats, until the
+    /// final report.
+    pub total_nodes: usize,
+    /// Number of the processed edges.
+    pub edges:       usize,
+    /// Number of the graph edges, from the stored header on import. The
+    /// number of the processed edges for the streamed formats, until the
+    /// final report.
+    pub total_edges: usize,
+}
+
+/// A callback receiving the [`Progress`] every [`REPORT_INTERVAL`] records,
+/// and once the nodes and edges are all processed.
+#[derive(Clone)]
+pub struct ProgressReporter(Arc<dyn Fn(Progress) + Send + Sync>);
+
+impl ProgressReporter {
+    /// Creates a reporter calling `report`.
+    #[inline]
+    pub fn new<F: Fn(Progress) + Send + Sync + 'static>(report: F) -> Self {
+        Self(Arc::new(report))
+    }
+}
+
+impl fmt::Debug for ProgressReporter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ProgressReporter").finish_non_exhaustive()
+    }
+}
+
+/// Counts the processed records of an import or export, reporting the
+/// progress and checking the cancellation every [`REPORT_INTERVAL`] records.
+pub(crate) struct Tracker<'a> {
+    progress: Progress,
+    reporter: Option<&'a ProgressReporter>,
+    cancel:   Option<&'a CancellationToken>,
+}
+
+impl<'a> Tracker<'a> {
+    /// Creates the tracker, reporting the empty progress.
+    pub(crate) fn new(
+        total_nodes: usize,
+        total_edges: usize,
+        reporter: Option<&'a ProgressReporter>,
+        cancel: Option<&'a CancellationToken>,
+    ) -> Result<Self, crate::error::Error> {
+        let tracker = Self {
+            progress: Progress {
+                total_nodes,
+                total_edges,
+                ..Default::default()
+            },
+            reporter,
+            cancel,
+        };
+        tracker.report()?;
+        Ok(tracker)
+    }
+
+    /// Counts a processed node.
+    #[inline]
+    pub(crate) fn node(&mut self) -> Result<(), crate::error::Error> {
+        self.progress.nodes += 1;
+        if self.progress.nodes % REPORT_INTERVAL == 0
+            || self.progress.nodes == self.progress.total_nodes
+        {
+            return self.report();
+        }
+        Ok(())
+    }
+
+    /// Counts a processed edge.
+    #[inline]
+    pub(crate) fn edge(&mut self) -> Result<(), crate::error::Error> {
+        self.progress.edges += 1;
+        if self.progress.edges % REPORT_INTERVAL == 0
+            || self.progress.edges == self.progress.total_edges
+        {
+            return self.report();
+        }
+        Ok(())
+    }
+
+    /// Reports the final progress if the totals were unknown, they are set to
+    /// the processed records.
+    pub(crate) fn finish(&mut self) -> Result<(), crate::error::Error> {
+        let progress = &mut self.progress;
+        if progress.nodes == progress.total_nodes && progress.edges == progress.total_edges {
+            return Ok(());
+        }
+
+        progress.total_nodes = progress.nodes;
+        progress.total_edges = progress.edges;
+        self.report()
+    }
+
+    /// Checks the cancellation then reports the progress.
+    fn report(&self) -> Result<(), crate::error::Error> {
+        if let Some(cancel) = self.cancel {
+            cancel.check()?;
+        }
+        if let Some(reporter) = self.reporter {
+            (reporter.0)(self.progress);
+        }
+        Ok(())
+    }
+}
@@ -9,10 +9,58 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
 // THE SOFTWARE.
 
+use std::{fmt, str::FromStr};
+
+use crate::error::GraphSerializationError;
+
 /// Relations of the graph
 #[repr(u8)]
-#[derive(Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
 pub enum Relation {
     Follow,
     Mute,
 }
+
+impl TryFrom<u8> for Relation {
+    type Error = GraphSerializationError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Follow),
+            1 => Ok(Self::Mute),
+            _ => Err(GraphSerializationError::InvalidRelation(value)),
+        }
+    }
+}
+
+impl Relation {
+    /// The relation name, `follow` or `mute`.
+    #[inline]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Follow => "follow",
+            Self::Mute => "mute",
+        }
+    }
+}
+
+impl fmt::Display for Relation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Relation {
+    type Err = GraphSerializationError;
+
+    /// Parses a relation name, see [`Relation::as_str`].
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "follow" => Ok(Self::Follow),
+            "mute" => Ok(Self::Mute),
+            _ => Err(GraphSerializationError::UnknownRelation(name.to_owned())),
+        }
+    }
+}
@@ -0,0 +1,128 @@
+// Copyright (c) 2026, Awiteb <a@4rs.nl>
+//     lightweight nostr Web of Trust library
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use petgraph::graph::NodeIndex;
+use serde::{
+    Deserialize,
+    Deserializer,
+    Serialize,
+    Serializer,
+    de::Error as _,
+    ser::{Error as _, SerializeSeq, SerializeStruct},
+};
+
+use crate::{WotGraph, options::ImportOptions, parser::GraphBuilder, relations::Relation};
+
+/// The graph nodes, serialized without collecting them.
+struct Nodes<'a>(&'a crate::GraphType);
+
+impl Serialize for Nodes<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.0.raw_nodes().iter().map(|node| node.weight))
+    }
+}
+
+/// The graph edges as `(source, relation, target)`, serialized without
+/// collecting them.
+struct Edges<'a>(&'a crate::GraphType);
+
+impl Serialize for Edges<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut edges = serializer.serialize_seq(Some(self.0.edge_count()))?;
+        for edge in self.0.raw_edges() {
+            let relation = Relation::try_from(edge.weight).map_err(S::Error::custom)?;
+            edges.serialize_element(&(
+                edge.source().index() as u32,
+                relation,
+                edge.target().index() as u32,
+            ))?;
+        }
+        edges.end()
+    }
+}
+
+/// The deserialized graph, before checking the edges nodes.
+#[derive(Deserialize)]
+#[serde(rename = "WotGraph")]
+struct RawGraph {
+    nodes: Vec<u64>,
+    edges: Vec<(u32, Relation, u32)>,
+}
+
+/// Serializes the graph as a struct of the node weights (public key hashes)
+/// and the edges as `(source index, relation, target index)` tuples, the
+/// relation is serialized as its name. The node indices and the edges order
+/// are preserved.
+impl Serialize for WotGraph {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut graph = serializer.serialize_struct("WotGraph", 2)?;
+        graph.serialize_field("nodes", &Nodes(&self.inner))?;
+        graph.serialize_field("edges", &Edges(&self.inner))?;
+        graph.end()
+    }
+}
+
+/// Deserializes a graph serialized by [`WotGraph`]'s [`Serialize`]
+/// implementation. Fails if an edge refers to a missing node or has an unknown
+/// relation.
+impl<'de> Deserialize<'de> for WotGraph {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = RawGraph::deserialize(deserializer)?;
+        let mut graph = WotGraph::with_capacity(raw.nodes.len(), raw.edges.len());
+        for weight in raw.nodes {
+            graph.inner.add_node(weight);
+        }
+
+        for (source, relation, target) in raw.edges {
+            for idx in [source, target] {
+                if idx as usize >= graph.inner.node_count() {
+                    return Err(D::Error::custom(format_args!(
+                        "edge refers to a missing node index {idx}"
+                    )));
+                }
+            }
+            graph.inner.add_edge(
+                NodeIndex::new(source as usize),
+                NodeIndex::new(target as usize),
+                relation as u8,
+            );
+        }
+
+        Ok(graph)
+    }
+}
+
+/// Deserializes a graph serialized by [`WotGraph`]'s [`Serialize`]
+/// implementation using the import options. The limits are checked once the
+/// data is deserialized, before building the graph.
+pub fn deserialize_graph<'de, D: Deserializer<'de>>(
+    deserializer: D,
+    options: &ImportOptions,
+) -> Result<crate::GraphType, D::Error> {
+    let raw = RawGraph::deserialize(deserializer)?;
+    build_graph(raw, options).map_err(D::Error::custom)
+}
+
+/// Builds the deserialized graph.
+fn build_graph(
+    raw: RawGraph,
+    options: &ImportOptions,
+) -> Result<crate::GraphType, crate::error::Error> {
+    let mut graph = GraphBuilder::with_counts(raw.nodes.len(), raw.edges.len(), options)?;
+    for weight in raw.nodes {
+        graph.add_node(weight)?;
+    }
+    for (source, relation, target) in raw.edges {
+        let source = graph.node_index(source.into())?;
+        graph.add_indexed_edge(source, relation as u8, target.into())?;
+    }
+    graph.finish()
+}
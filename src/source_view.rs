@@ -0,0 +1,160 @@
+// Copyright (c) 2026, Awiteb <a@4rs.nl>
+//     lightweight nostr Web of Trust library
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use std::collections::{HashMap, HashSet};
+
+use petgraph::{Direction, graph::NodeIndex};
+
+use crate::{
+    WotGraph,
+    relations::Relation,
+    traits::{basic::BasicOperationsExt, dump_wot},
+};
+
+/// A cached view of a source's network, to answer many queries from the
+/// same source without traversing the graph each time.
+///
+/// The view computes the hop levels of the source once, then every target
+/// query only walks the target's incoming edges. The view is refreshed
+/// automatically before each query when the graph was mutated in a way that
+/// changes the source's network, e.g. a new `Follow` edge added by
+/// [`WotGraph::add_edge`] or [`WotGraph::add_unique_edge`] from a node within
+/// the hops. Any access to [`WotGraph::inner_mut`] rebuilds the view.
+///
+/// Querying the view with another graph than the one it was created from
+/// rebuilds it for that graph.
+///
+/// ```rust
+/// use nostr_wot::{WotGraph, relations::Relation};
+///
+/// let mut graph = WotGraph::new();
+/// let node1 = graph.add_node(1).unwrap();
+/// let node2 = graph.add_node(2).unwrap();
+/// let node3 = graph.add_node(3).unwrap();
+/// graph.add_edge(node1, node2, Relation::Follow).unwrap();
+///
+/// let mut view = graph.source_view(node1, 2);
+/// assert_eq!(view.dump_wot(&graph, node3), 0);
+///
+/// graph.add_edge(node2, node3, Relation::Follow).unwrap();
+/// assert_eq!(view.dump_wot(&graph, node3), 1);
+/// ```
+#[derive(Debug, Clone)]
+pub struct SourceView {
+    /// The source node.
+    source:   NodeIndex,
+    /// Maximum number of hops from the source.
+    max_hops: u8,
+    /// The nodes within `max_hops` from the source and their hop distance.
+    hops:     HashMap<NodeIndex, u8>,
+    /// The identity of the graph the view was built from.
+    graph:    u64,
+    /// The graph revision the view was built from.
+    revision: u64,
+    /// Number of the graph edges already seen by the view.
+    edges:    usize,
+}
+
+impl SourceView {
+    /// Creates a new view of the `source` network within `max_hops`.
+    pub fn new(graph: &WotGraph, source: NodeIndex, max_hops: u8) -> Self {
+        Self {
+            source,
+            max_hops,
+            hops: graph.inner.nodes_in_hops(source, max_hops),
+            graph: graph.id,
+            revision: graph.revision,
+            edges: graph.inner.edge_count(),
+        }
+    }
+
+    /// The source node of the view.
+    #[inline]
+    pub fn source(&self) -> NodeIndex {
+        self.source
+    }
+
+    /// Maximum number of hops from the source.
+    #[inline]
+    pub fn max_hops(&self) -> u8 {
+        self.max_hops
+    }
+
+    /// Returns `true` if the graph changed in a way that requires rebuilding
+    /// the view, or it's not the graph the view was built from.
+    pub fn is_stale(&self, graph: &WotGraph) -> bool {
+        if self.graph != graph.id
+            || self.revision != graph.revision
+            || self.edges > graph.inner.edge_count()
+        {
+            return true;
+        }
+
+        // Only a new `Follow` edge from a node before the last hop can extend
+        // the network, any other edge is read during the queries
+        graph.inner.raw_edges()[self.edges..].iter().any(|edge| {
+            edge.weight == Relation::Follow as u8
+                && self
+                    .hops
+                    .get(&edge.source())
+                    .is_some_and(|hop| *hop < self.max_hops)
+        })
+    }
+
+    /// Rebuilds the view if it's stale, see [`SourceView::is_stale`].
+    pub fn refresh(&mut self, graph: &WotGraph) {
+        if self.is_stale(graph) {
+            *self = Self::new(graph, self.source, self.max_hops);
+        } else {
+            self.edges = graph.inner.edge_count();
+        }
+    }
+
+    /// Returns the hop distance of `node` from the source, or `None` if it's
+    /// not within `max_hops`.
+    pub fn hop(&mut self, graph: &WotGraph, node: NodeIndex) -> Option<u8> {
+        self.refresh(graph);
+        self.hops.get(&node).copied()
+    }
+
+    /// Counts how many nodes in the source's network have the given
+    /// `relation` with the target. Same as
+    /// [`WotGraph::count_neighbors_in_hops`].
+    ///
+    /// # Time Complexity
+    /// O(E) where E is the target's incoming edges
+    pub fn count_neighbors_in_hops(
+        &mut self,
+        graph: &WotGraph,
+        target: NodeIndex,
+        relation: Relation,
+    ) -> usize {
+        self.refresh(graph);
+        if graph.inner.raw_nodes().get(target.index()).is_none() {
+            return 0;
+        }
+
+        graph
+            .inner
+            .get_matches_neighbors(target, relation, Direction::Incoming)
+            .filter(|node| self.hops.contains_key(node))
+            .collect::<HashSet<_>>()
+            .len()
+    }
+
+    /// Counts the trust score between the source and the target. Same as
+    /// [`WotGraph::dump_wot`].
+    pub fn dump_wot(&mut self, graph: &WotGraph, target: NodeIndex) -> isize {
+        let follows = self.count_neighbors_in_hops(graph, target, Relation::Follow);
+        let mutes = self.count_neighbors_in_hops(graph, target, Relation::Mute);
+        dump_wot::score(follows, mutes)
+    }
+}
@@ -4,7 +4,69 @@ use petgraph::{Direction, graph::NodeIndex};
 use crate::{WotGraph, relations::Relation};
 
 mod export_import {
+    use std::sync::{Arc, Mutex};
+
     use super::*;
+    use crate::{
+        error::{Error, GraphSerializationError, Limit},
+        options::{
+            CapacityPolicy,
+            Codec,
+            DuplicatePolicy,
+            Encoding,
+            ExportOptions,
+            ImportOptions,
+            MAX_PREALLOCATION,
+        },
+        progress::{CancellationToken, Progress},
+        utils::HASH_SEED,
+    };
+
+    /// A version 1 graph with the given header and body, and a valid checksum.
+    fn raw_graph(header: [u64; 4], body: &[u8]) -> Vec<u8> {
+        let mut data = b"NWOT".to_vec();
+        data.extend(1u32.to_le_bytes());
+        for value in header {
+            data.extend(value.to_le_bytes());
+        }
+        data.extend(body);
+        data.extend(xxhash_rust::xxh64::xxh64(&data, HASH_SEED).to_le_bytes());
+        data
+    }
+
+    /// Sorted edges of the graph as `(source, relation, target)` weights.
+    fn edge_weights(graph: &WotGraph) -> Vec<(u64, u8, u64)> {
+        let mut edges: Vec<_> = graph
+            .inner
+            .raw_edges()
+            .iter()
+            .map(|edge| {
+                (
+                    graph.inner[edge.source()],
+                    edge.weight,
+                    graph.inner[edge.target()],
+                )
+            })
+            .collect();
+        edges.sort_unstable();
+        edges
+    }
+
+    /// A graph with follows, mutes and a duplicated edge.
+    fn compact_graph() -> WotGraph {
+        let mut graph = WotGraph::new();
+        for i in 1..=300 {
+            graph.add_node(i).unwrap();
+        }
+        for i in 1..300 {
+            let source = node_idx(&graph, i);
+            graph.add_edge(source, node_idx(&graph, i + 1), Relation::Follow);
+            graph.add_edge(source, node_idx(&graph, 301 - i), Relation::Mute);
+            graph.add_edge(node_idx(&graph, 300), source, Relation::Follow);
+        }
+        graph.add_edge(node_idx(&graph, 1), node_idx(&graph, 2), Relation::Follow);
+        graph
+    }
 
     #[test]
     fn empty() {
@@ -78,6 +140,599 @@ mod export_import {
         assert!(WotGraph::import(&invalid_data).is_err());
     }
 
+    #[test]
+    fn magic_header() {
+        let exported_graph = WotGraph::new().export().unwrap();
+        assert_eq!(&exported_graph[..4], b"NWOT");
+        assert_eq!(&exported_graph[4..8], &1u32.to_le_bytes());
+    }
+
+    #[test]
+    fn version_zero() {
+        // The format before versioning: capacities, counts, nodes and edges
+        let mut legacy = Vec::new();
+        for value in [4u64, 4, 2, 1, 10, 20, 10] {
+            legacy.extend(value.to_le_bytes());
+        }
+        legacy.push(Relation::Mute as u8);
+        legacy.extend(20u64.to_le_bytes());
+
+        let graph = WotGraph::import(&legacy).unwrap();
+        assert_eq!(graph.inner.raw_nodes().len(), 2);
+        assert_eq!(graph.inner.raw_edges().len(), 1);
+        assert_eq!(
+            graph.dump_wot(node_idx(&graph, 10), node_idx(&graph, 20), 1),
+            -1
+        );
+    }
+
+    #[test]
+    fn version_zero_gzip_magic() {
+        // A nodes capacity starting with the gzip magic bytes
+        let mut legacy = Vec::new();
+        for value in [0x8b1f_u64, 4, 2, 1, 10, 20, 10] {
+            legacy.extend(value.to_le_bytes());
+        }
+        legacy.push(Relation::Follow as u8);
+        legacy.extend(20u64.to_le_bytes());
+        assert_eq!(&legacy[..2], &[0x1f, 0x8b]);
+
+        for graph in [
+            WotGraph::import(&legacy).unwrap(),
+            WotGraph::import_auto(&legacy).unwrap(),
+        ] {
+            assert_eq!(graph.inner.raw_nodes().len(), 2);
+            assert_eq!(
+                graph.dump_wot(node_idx(&graph, 10), node_idx(&graph, 20), 1),
+                1
+            );
+        }
+    }
+
+    #[test]
+    fn gzip_as_raw() {
+        let exported_graph = WotGraph::new().export_gzip().unwrap();
+        assert!(matches!(
+            WotGraph::import(&exported_graph),
+            Err(Error::GraphSerializationError(
+                GraphSerializationError::NotAWotGraph
+            ))
+        ));
+    }
+
+    #[test]
+    fn not_a_graph() {
+        // Counts larger than the capacities
+        let mut data = [0u8; 64];
+        data[16] = 1;
+        assert!(matches!(
+            WotGraph::import(&data),
+            Err(Error::GraphSerializationError(
+                GraphSerializationError::NotAWotGraph
+            ))
+        ));
+    }
+
+    #[test]
+    fn unsupported_version() {
+        let mut exported_graph = WotGraph::new().export().unwrap();
+        exported_graph[4..8].copy_from_slice(&99u32.to_le_bytes());
+        assert!(matches!(
+            WotGraph::import(&exported_graph),
+            Err(Error::GraphSerializationError(
+                GraphSerializationError::UnsupportedVersion(99)
+            ))
+        ));
+    }
+
+    #[test]
+    fn checksum_mismatch() {
+        let mut graph = WotGraph::new();
+        let p1 = graph.add_node(1).unwrap();
+        let p2 = graph.add_node(2).unwrap();
+        graph.add_edge(p1, p2, Relation::Follow);
+        let mut exported_graph = graph.export().unwrap();
+        // Flip the edge relation
+        let relation = exported_graph.len() - 8 - 9;
+        exported_graph[relation] = Relation::Mute as u8;
+
+        assert!(matches!(
+            WotGraph::import(&exported_graph),
+            Err(Error::GraphSerializationError(
+                GraphSerializationError::ChecksumMismatch(..)
+            ))
+        ));
+    }
+
+    #[test]
+    fn missing_checksum() {
+        let mut graph = WotGraph::new();
+        graph.add_node(1).unwrap();
+        let exported_graph = graph.export().unwrap();
+
+        assert!(matches!(
+            WotGraph::import(&exported_graph[..exported_graph.len() - 8]),
+            Err(Error::GraphSerializationError(
+                GraphSerializationError::InsufficientData(_)
+            ))
+        ));
+    }
+
+    #[test]
+    fn compact_roundtrip() {
+        let graph = compact_graph();
+        let options = ExportOptions::default().encoding(Encoding::Compact);
+        let exported = graph.export_with(&options).unwrap();
+        assert_eq!(&exported[4..8], &2u32.to_le_bytes());
+
+        let imported = WotGraph::import(&exported).unwrap();
+        assert_eq!(imported.inner.raw_nodes().len(), 300);
+        assert_eq!(edge_weights(&imported), edge_weights(&graph));
+        assert_eq!(imported.inner.capacity().0, graph.inner.capacity().0);
+    }
+
+    #[test]
+    fn compact_empty() {
+        let options = ExportOptions::default().encoding(Encoding::Compact);
+        let exported = WotGraph::new().export_with(&options).unwrap();
+        let imported = WotGraph::import(&exported).unwrap();
+        assert_eq!(imported.inner.raw_nodes().len(), 0);
+        assert_eq!(imported.inner.raw_edges().len(), 0);
+    }
+
+    #[test]
+    fn compact_smaller_than_standard() {
+        let graph = compact_graph();
+        let standard = graph.export().unwrap();
+        let compact = graph
+            .export_with(&ExportOptions::default().encoding(Encoding::Compact))
+            .unwrap();
+        assert!(compact.len() * 2 < standard.len());
+    }
+
+    #[test]
+    fn compact_gzip() {
+        let graph = compact_graph();
+        let options = ExportOptions::default().encoding(Encoding::Compact);
+        let exported = graph.export_compressed_with(&options).unwrap();
+        let imported = WotGraph::import_gzip(&exported).unwrap();
+        assert_eq!(edge_weights(&imported), edge_weights(&graph));
+    }
+
+    #[test]
+    fn compact_invalid_node_index() {
+        let mut graph = WotGraph::new();
+        let p1 = graph.add_node(1).unwrap();
+        let p2 = graph.add_node(2).unwrap();
+        graph.add_edge(p1, p2, Relation::Follow);
+        let options = ExportOptions::default().encoding(Encoding::Compact);
+        let mut exported_graph = graph.export_with(&options).unwrap();
+        // The target index delta is the last byte before the checksum
+        let target = exported_graph.len() - 8 - 1;
+        exported_graph[target] = 5;
+
+        assert!(matches!(
+            WotGraph::import(&exported_graph),
+            Err(Error::GraphSerializationError(
+                GraphSerializationError::NodeNotFound(5)
+            ))
+        ));
+    }
+
+    #[test]
+    fn compact_truncated() {
+        let graph = compact_graph();
+        let options = ExportOptions::default().encoding(Encoding::Compact);
+        let exported_graph = graph.export_with(&options).unwrap();
+
+        assert!(matches!(
+            WotGraph::import(&exported_graph[..exported_graph.len() - 20]),
+            Err(Error::GraphSerializationError(
+                GraphSerializationError::InsufficientData(_)
+            ))
+        ));
+    }
+
+    #[test]
+    fn compact_varint_overflow() {
+        // A nodes capacity varint with 2 bits in its 10th byte
+        let mut data = b"NWOT".to_vec();
+        data.extend(2u32.to_le_bytes());
+        data.extend([0xff; 9]);
+        data.push(0x02);
+        data.extend([0; 3]);
+
+        assert!(matches!(
+            WotGraph::import(&data),
+            Err(Error::GraphSerializationError(
+                GraphSerializationError::InvalidFormat
+            ))
+        ));
+    }
+
+    #[test]
+    fn hostile_capacities_are_clamped() {
+        let data = raw_graph([u64::MAX, u64::MAX, 0, 0], &[]);
+        let graph = WotGraph::import(&data).unwrap();
+        assert_eq!(graph.inner.capacity().0, MAX_PREALLOCATION);
+        assert_eq!(graph.inner.capacity().1, MAX_PREALLOCATION);
+
+        let options = ImportOptions::default().max_nodes(10).max_edges(20);
+        let graph = WotGraph::import_with(&data, &options).unwrap();
+        assert_eq!(graph.inner.capacity().0, 10);
+        assert_eq!(graph.inner.capacity().1, 20);
+    }
+
+    #[test]
+    fn ignored_and_trusted_capacities() {
+        let data = raw_graph([100, 200, 0, 0], &[]);
+
+        let options = ImportOptions::default().capacity(CapacityPolicy::Ignore);
+        let graph = WotGraph::import_with(&data, &options).unwrap();
+        assert_eq!(graph.inner.capacity(), (0, 0));
+
+        let options = ImportOptions::default().capacity(CapacityPolicy::Trust);
+        let graph = WotGraph::import_with(&data, &options).unwrap();
+        assert_eq!(graph.inner.capacity(), (100, 200));
+    }
+
+    #[test]
+    fn hostile_counts() {
+        let data = raw_graph([0, 0, u64::MAX, u64::MAX], &[]);
+        assert!(matches!(
+            WotGraph::import(&data),
+            Err(Error::GraphSerializationError(
+                GraphSerializationError::InsufficientData(usize::MAX)
+            ))
+        ));
+    }
+
+    #[test]
+    fn limit_exceeded() {
+        let graph = compact_graph();
+        for options in [
+            ExportOptions::default(),
+            ExportOptions::default().encoding(Encoding::Compact),
+        ] {
+            let exported_graph = graph.export_with(&options).unwrap();
+
+            assert!(matches!(
+                WotGraph::import_with(&exported_graph, &ImportOptions::default().max_nodes(299)),
+                Err(Error::GraphSerializationError(
+                    GraphSerializationError::LimitExceeded(Limit::Nodes, 300, 299)
+                ))
+            ));
+            assert!(matches!(
+                WotGraph::import_with(&exported_graph, &ImportOptions::default().max_edges(10)),
+                Err(Error::GraphSerializationError(
+                    GraphSerializationError::LimitExceeded(Limit::Edges, _, 10)
+                ))
+            ));
+            assert!(
+                WotGraph::import_with(
+                    &exported_graph,
+                    &ImportOptions::default().max_nodes(300).max_edges(1000)
+                )
+                .is_ok()
+            );
+        }
+    }
+
+    #[test]
+    fn byte_by_byte() {
+        /// A reader returning a single byte per read.
+        struct ByteReader<'a>(&'a [u8]);
+
+        impl std::io::Read for ByteReader<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                let len = buf.len().min(1);
+                self.0.read(&mut buf[..len])
+            }
+        }
+
+        let graph = compact_graph();
+        for options in [
+            ExportOptions::default(),
+            ExportOptions::default().encoding(Encoding::Compact),
+        ] {
+            let exported_graph = graph.export_with(&options).unwrap();
+            let imported =
+                crate::parser::import_graph(ByteReader(&exported_graph), &ImportOptions::default())
+                    .unwrap();
+            assert_eq!(
+                edge_weights(&WotGraph::from_inner(imported)),
+                edge_weights(&graph)
+            );
+        }
+    }
+
+    #[test]
+    fn duplicate_nodes_merged() {
+        let mut graph = WotGraph::new();
+        let p1 = graph.add_node(1).unwrap();
+        let p2 = graph.add_node(2).unwrap();
+        let p2_dup = graph.add_node(2).unwrap();
+        graph.add_edge(p1, p2, Relation::Follow);
+        graph.add_edge(p2_dup, p1, Relation::Mute);
+
+        for options in [
+            ExportOptions::default(),
+            ExportOptions::default().encoding(Encoding::Compact),
+        ] {
+            let imported = WotGraph::import(&graph.export_with(&options).unwrap()).unwrap();
+            assert_eq!(imported.inner.raw_nodes().len(), 2);
+            assert_eq!(imported.inner.raw_edges().len(), 2);
+
+            let p1 = node_idx(&imported, 1);
+            let p2 = node_idx(&imported, 2);
+            assert_eq!(p2.index(), 1);
+            assert!(imported.inner.contains_edge(p1, p2));
+            assert!(imported.inner.contains_edge(p2, p1));
+        }
+    }
+
+    #[test]
+    fn duplicate_nodes_error() {
+        let mut graph = WotGraph::new();
+        graph.add_node(1).unwrap();
+        graph.add_node(2).unwrap();
+        graph.add_node(1).unwrap();
+        let options = ImportOptions::default().duplicates(DuplicatePolicy::Error);
+
+        for encoding in [Encoding::Standard, Encoding::Compact] {
+            let exported_graph = graph
+                .export_with(&ExportOptions::default().encoding(encoding))
+                .unwrap();
+            assert!(matches!(
+                WotGraph::import_with(&exported_graph, &options),
+                Err(Error::GraphSerializationError(
+                    GraphSerializationError::DuplicateNode(1)
+                ))
+            ));
+        }
+    }
+
+    #[test]
+    fn auto_detection() {
+        let graph = compact_graph();
+        let compact = ExportOptions::default().encoding(Encoding::Compact);
+        for exported_graph in [
+            graph.export().unwrap(),
+            graph.export_gzip().unwrap(),
+            graph.export_with(&compact).unwrap(),
+            graph.export_compressed_with(&compact).unwrap(),
+        ] {
+            let imported = WotGraph::import_auto(&exported_graph).unwrap();
+            assert_eq!(edge_weights(&imported), edge_weights(&graph));
+        }
+    }
+
+    #[test]
+    fn auto_detection_file() {
+        let graph = compact_graph();
+        let path = std::env::temp_dir().join(format!("nostr-wot-auto-{}.gz", std::process::id()));
+        graph.export_to_file_gzip(&path).unwrap();
+        let imported = WotGraph::import_from_file_auto(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(edge_weights(&imported.unwrap()), edge_weights(&graph));
+    }
+
+    #[test]
+    fn auto_detection_invalid() {
+        assert!(WotGraph::import_auto(&[]).is_err());
+        assert!(WotGraph::import_auto(&[0x1f, 0x8b, 0, 0]).is_err());
+    }
+
+    #[test]
+    fn gzip_level() {
+        let graph = compact_graph();
+        let stored = graph
+            .export_compressed_with(&ExportOptions::default().level(0))
+            .unwrap();
+        let best = graph
+            .export_compressed_with(&ExportOptions::default().codec(Codec::Gzip).level(9))
+            .unwrap();
+        assert!(best.len() < stored.len());
+        assert_eq!(
+            edge_weights(&WotGraph::import_gzip(&stored).unwrap()),
+            edge_weights(&graph)
+        );
+
+        assert!(matches!(
+            graph.export_compressed_with(&ExportOptions::default().level(10)),
+            Err(Error::InvalidCompressionLevel(Codec::Gzip, 10))
+        ));
+    }
+
+    #[cfg(feature = "lz4")]
+    #[test]
+    fn lz4_roundtrip() {
+        let graph = compact_graph();
+        let exported_graph = graph
+            .export_compressed_with(&ExportOptions::default().codec(Codec::Lz4))
+            .unwrap();
+        assert_eq!(&exported_graph[..4], &[0x04, 0x22, 0x4d, 0x18]);
+        assert!(matches!(
+            WotGraph::import(&exported_graph),
+            Err(Error::GraphSerializationError(
+                GraphSerializationError::NotAWotGraph
+            ))
+        ));
+
+        let imported = WotGraph::import_auto(&exported_graph).unwrap();
+        assert_eq!(edge_weights(&imported), edge_weights(&graph));
+
+        assert!(matches!(
+            graph.export_compressed_with(&ExportOptions::default().codec(Codec::Lz4).level(4)),
+            Err(Error::InvalidCompressionLevel(Codec::Lz4, 4))
+        ));
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn zstd_roundtrip() {
+        let graph = compact_graph();
+        for level in [0, 1] {
+            let options = ExportOptions::default()
+                .encoding(Encoding::Compact)
+                .codec(Codec::Zstd)
+                .level(level);
+            let exported_graph = graph.export_compressed_with(&options).unwrap();
+            assert_eq!(&exported_graph[..4], &[0x28, 0xb5, 0x2f, 0xfd]);
+
+            let imported = WotGraph::import_auto(&exported_graph).unwrap();
+            assert_eq!(edge_weights(&imported), edge_weights(&graph));
+        }
+
+        // Only the fastest level is implemented
+        assert!(matches!(
+            graph.export_compressed_with(&ExportOptions::default().codec(Codec::Zstd).level(3)),
+            Err(Error::InvalidCompressionLevel(Codec::Zstd, 3))
+        ));
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn async_same_as_sync() {
+        let graph = compact_graph();
+        for options in [
+            ExportOptions::default(),
+            ExportOptions::default().encoding(Encoding::Compact),
+        ] {
+            let mut exported_graph = Vec::new();
+            graph
+                .export_async(&mut exported_graph, &options)
+                .await
+                .unwrap();
+            assert_eq!(exported_graph, graph.export_with(&options).unwrap());
+
+            let imported =
+                WotGraph::import_async(exported_graph.as_slice(), &ImportOptions::default())
+                    .await
+                    .unwrap();
+            assert_eq!(edge_weights(&imported), edge_weights(&graph));
+        }
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn async_gzip() {
+        let graph = compact_graph();
+        let options = ExportOptions::default().encoding(Encoding::Compact);
+        let mut exported_graph = Vec::new();
+        graph
+            .export_gzip_async(&mut exported_graph, &options)
+            .await
+            .unwrap();
+
+        let imported = WotGraph::import_gzip(&exported_graph).unwrap();
+        assert_eq!(edge_weights(&imported), edge_weights(&graph));
+        let imported = WotGraph::import_async(exported_graph.as_slice(), &ImportOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(edge_weights(&imported), edge_weights(&graph));
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn async_version_zero_gzip_magic() {
+        let mut legacy = Vec::new();
+        for value in [0x8b1f_u64, 4, 1, 0, 10] {
+            legacy.extend(value.to_le_bytes());
+        }
+
+        let imported = WotGraph::import_async(legacy.as_slice(), &ImportOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(imported.inner.raw_nodes().len(), 1);
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn async_file() {
+        let graph = compact_graph();
+        let path = std::env::temp_dir().join(format!("nostr-wot-async-{}.gz", std::process::id()));
+        graph
+            .export_to_file_gzip_async(&path, &ExportOptions::default())
+            .await
+            .unwrap();
+        let imported = WotGraph::import_from_file_async(&path, &ImportOptions::default()).await;
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(edge_weights(&imported.unwrap()), edge_weights(&graph));
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn async_checksum_mismatch() {
+        let mut exported_graph = compact_graph().export().unwrap();
+        let last = exported_graph.len() - 1;
+        exported_graph[last] ^= 1;
+
+        assert!(matches!(
+            WotGraph::import_async(exported_graph.as_slice(), &ImportOptions::default()).await,
+            Err(Error::GraphSerializationError(
+                GraphSerializationError::ChecksumMismatch(..)
+            ))
+        ));
+    }
+
+    #[test]
+    fn progress_reports() {
+        let graph = compact_graph();
+        for encoding in [Encoding::Standard, Encoding::Compact] {
+            let exported_reports = Arc::new(Mutex::new(Vec::new()));
+            let reports = Arc::clone(&exported_reports);
+            let options = ExportOptions::default()
+                .encoding(encoding)
+                .progress(move |progress| reports.lock().unwrap().push(progress));
+            let exported_graph = graph.export_with(&options).unwrap();
+
+            let imported_reports = Arc::new(Mutex::new(Vec::new()));
+            let reports = Arc::clone(&imported_reports);
+            let options = ImportOptions::default()
+                .progress(move |progress| reports.lock().unwrap().push(progress));
+            WotGraph::import_with(&exported_graph, &options).unwrap();
+
+            for reports in [exported_reports, imported_reports] {
+                let reports = reports.lock().unwrap();
+                let total = Progress {
+                    nodes:       300,
+                    total_nodes: 300,
+                    edges:       898,
+                    total_edges: 898,
+                };
+                assert_eq!(
+                    reports.first(),
+                    Some(&Progress {
+                        nodes: 0,
+                        edges: 0,
+                        ..total
+                    })
+                );
+                assert_eq!(reports.last(), Some(&total));
+            }
+        }
+    }
+
+    #[test]
+    fn cancelled_import_export() {
+        let graph = compact_graph();
+        let exported_graph = graph.export().unwrap();
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+
+        assert!(matches!(
+            graph.export_with(&ExportOptions::default().cancel(cancel.clone())),
+            Err(Error::Cancelled)
+        ));
+        assert!(matches!(
+            WotGraph::import_with(&exported_graph, &ImportOptions::default().cancel(cancel)),
+            Err(Error::Cancelled)
+        ));
+    }
+
     #[test]
     fn invalid_gzip() {
         let invalid_data = [7; 60]; // Not a valid gzipped graph
@@ -146,7 +801,10 @@ mod export_import {
 }
 
 mod basic_operations {
+    use std::collections::HashSet;
+
     use super::*;
+    use crate::traits::basic::{BasicOperationsExt, PARALLEL_FRONTIER_THRESHOLD, TraversalScratch};
 
     #[test]
     fn neighbors_no_outgoing() {
@@ -365,6 +1023,108 @@ mod basic_operations {
             3
         );
     }
+
+    #[test]
+    fn reused_scratch() {
+        let mut graph = WotGraph::new();
+        let p1 = graph.add_node(1).unwrap();
+        let p2 = graph.add_node(2).unwrap();
+        let p3 = graph.add_node(3).unwrap();
+        graph.add_edge(p1, p2, Relation::Follow);
+        graph.add_edge(p2, p3, Relation::Follow);
+        graph.add_edge(p1, p3, Relation::Mute);
+
+        let mut scratch = TraversalScratch::new();
+        for _ in 0..3 {
+            assert_eq!(
+                graph
+                    .inner
+                    .count_matches_in_hops_with(&mut scratch, p1, p3, Relation::Follow, 1),
+                1
+            );
+            assert_eq!(
+                graph
+                    .inner
+                    .count_matches_in_hops_with(&mut scratch, p2, p3, Relation::Mute, 1),
+                0
+            );
+        }
+    }
+
+    /// The previous `HashSet` based traversal, kept as a reference.
+    fn count_matches_in_hops_hashset(
+        graph: &WotGraph,
+        source: NodeIndex,
+        target: NodeIndex,
+        relation: Relation,
+        max_hops: u8,
+    ) -> usize {
+        let target_incoming: HashSet<NodeIndex> = graph
+            .neighbors(target, relation, Direction::Incoming)
+            .collect();
+        let mut visited = HashSet::new();
+        let mut current_level = vec![source];
+        let mut count = 0;
+        for hop in 0..=max_hops {
+            for node in &current_level {
+                if visited.insert(*node) && target_incoming.contains(node) {
+                    count += 1;
+                }
+            }
+            if hop == max_hops {
+                break;
+            }
+            current_level = current_level
+                .iter()
+                .flat_map(|idx| graph.neighbors(*idx, Relation::Follow, Direction::Outgoing))
+                .filter(|idx| !visited.contains(idx))
+                .collect();
+        }
+        count
+    }
+
+    #[test]
+    fn parallel_frontier_expansion() {
+        const NODES: u64 = 20_000;
+
+        let mut graph = WotGraph::with_capacity(NODES as usize, NODES as usize * 4);
+        for i in 0..NODES {
+            graph.add_node(i).unwrap();
+        }
+        let source = NodeIndex::new(0);
+        // A first level larger than the parallel threshold
+        for i in 1..=PARALLEL_FRONTIER_THRESHOLD as u64 * 2 {
+            graph.add_edge(source, NodeIndex::new(i as usize), Relation::Follow);
+        }
+        for i in 1..NODES {
+            for j in [i * 7 + 1, i * 31 + 2, i * 131 + 5] {
+                graph.add_edge(
+                    NodeIndex::new(i as usize),
+                    NodeIndex::new((j % NODES) as usize),
+                    Relation::Follow,
+                );
+            }
+        }
+
+        for max_hops in 0..4 {
+            let hops = graph.inner.nodes_in_hops(source, max_hops);
+            assert_eq!(hops, graph.multi_source_hops(&[source], max_hops)[0]);
+
+            for target in [1, 77, 4242, 19_999] {
+                let target = NodeIndex::new(target);
+                assert_eq!(
+                    graph.count_neighbors_in_hops(source, target, Relation::Follow, max_hops),
+                    count_matches_in_hops_hashset(
+                        &graph,
+                        source,
+                        target,
+                        Relation::Follow,
+                        max_hops
+                    )
+                );
+            }
+        }
+    }
 }
 
 mod dump_wot {
@@ -576,6 +1336,1532 @@ mod dump_wot {
         assert_eq!(graph.dump_wot(p1, p7, 3), 2);
     }
 }
+mod hyper_anf {
+    use super::*;
+
+    #[test]
+    fn empty_graph() {
+        let graph = WotGraph::new();
+        assert!(graph.approx_neighborhood_sizes(3, 10).is_empty());
+    }
+
+    #[test]
+    fn zero_hops() {
+        let mut graph = WotGraph::new();
+        let p1 = graph.add_node(1).unwrap();
+        let p2 = graph.add_node(2).unwrap();
+        graph.add_edge(p1, p2, Relation::Follow);
+
+        let sizes = graph.approx_neighborhood_sizes(0, 10);
+        assert_eq!(sizes[p1.index()].round(), 1.0);
+        assert_eq!(sizes[p2.index()].round(), 1.0);
+    }
+
+    #[test]
+    fn chain() {
+        let mut graph = WotGraph::new();
+        for i in 1..=10 {
+            graph.add_node(i).unwrap();
+        }
+        for i in 1..10 {
+            graph.add_edge(
+                node_idx(&graph, i),
+                node_idx(&graph, i + 1),
+                Relation::Follow,
+            );
+        }
+
+        let sizes = graph.approx_neighborhood_sizes(3, 10);
+        assert_eq!(sizes[node_idx(&graph, 1).index()].round(), 4.0);
+        assert_eq!(sizes[node_idx(&graph, 8).index()].round(), 3.0);
+        assert_eq!(sizes[node_idx(&graph, 10).index()].round(), 1.0);
+    }
+
+    #[test]
+    fn mutes_are_not_followed() {
+        let mut graph = WotGraph::new();
+        let p1 = graph.add_node(1).unwrap();
+        let p2 = graph.add_node(2).unwrap();
+        let p3 = graph.add_node(3).unwrap();
+        graph.add_edge(p1, p2, Relation::Mute);
+        graph.add_edge(p2, p3, Relation::Follow);
+
+        let sizes = graph.approx_neighborhood_sizes(2, 10);
+        assert_eq!(sizes[p1.index()].round(), 1.0);
+        assert_eq!(sizes[p2.index()].round(), 2.0);
+    }
+
+    #[test]
+    fn large_neighborhood_within_error() {
+        let mut graph = WotGraph::new();
+        let source = graph.add_node(0).unwrap();
+        // source -> 100 nodes -> 50 nodes each
+        for i in 1..=100 {
+            let hop1 = graph.add_node(i).unwrap();
+            graph.add_edge(source, hop1, Relation::Follow);
+            for j in 0..50 {
+                let hop2 = graph.add_node(i * 1000 + j).unwrap();
+                graph.add_edge(hop1, hop2, Relation::Follow);
+            }
+        }
+
+        let sizes = graph.approx_neighborhood_sizes(2, 12);
+        let exact = 1.0 + 100.0 + 100.0 * 50.0;
+        // 1.04 / sqrt(4096) ~= 1.6%, allow three standard errors
+        assert!((sizes[source.index()] - exact).abs() / exact < 0.05);
+    }
+
+    #[test]
+    fn cancelled() {
+        let mut graph = WotGraph::new();
+        let p1 = graph.add_node(1).unwrap();
+        let p2 = graph.add_node(2).unwrap();
+        graph.add_edge(p1, p2, Relation::Follow);
+        let cancel = crate::progress::CancellationToken::new();
+        assert_eq!(
+            graph
+                .approx_neighborhood_sizes_cancellable(2, 8, &cancel)
+                .unwrap(),
+            graph.approx_neighborhood_sizes(2, 8)
+        );
+
+        cancel.cancel();
+        assert!(matches!(
+            graph.approx_neighborhood_sizes_cancellable(2, 8, &cancel),
+            Err(crate::error::Error::Cancelled)
+        ));
+    }
+}
+
+mod top_k {
+    use super::*;
+
+    #[test]
+    fn k_zero() {
+        let mut graph = WotGraph::new();
+        let p1 = graph.add_node(1).unwrap();
+        let p2 = graph.add_node(2).unwrap();
+        graph.add_edge(p1, p2, Relation::Follow);
+
+        assert!(graph.top_k(p1, 3, 0).is_empty());
+    }
+
+    #[test]
+    fn missing_source() {
+        let graph = WotGraph::new();
+        assert!(graph.top_k(NodeIndex::new(3), 3, 10).is_empty());
+    }
+
+    #[test]
+    fn k_max() {
+        let mut graph = WotGraph::new();
+        let p1 = graph.add_node(1).unwrap();
+        let p2 = graph.add_node(2).unwrap();
+        graph.add_edge(p1, p2, Relation::Follow);
+
+        assert_eq!(graph.top_k(p1, 3, usize::MAX), vec![(p2, 1)]);
+    }
+
+    #[test]
+    fn excludes_source_and_untrusted() {
+        let mut graph = WotGraph::new();
+        let p1 = graph.add_node(1).unwrap();
+        let p2 = graph.add_node(2).unwrap();
+        let p3 = graph.add_node(3).unwrap();
+        let p4 = graph.add_node(4).unwrap();
+
+        graph.add_edge(p1, p2, Relation::Follow);
+        graph.add_edge(p2, p1, Relation::Follow);
+        graph.add_edge(p1, p3, Relation::Mute);
+        graph.add_edge(p1, p4, Relation::Follow);
+        graph.add_edge(p2, p4, Relation::Mute);
+
+        assert_eq!(graph.top_k(p1, 2, 10), vec![(p2, 1)]);
+    }
+
+    #[test]
+    fn duplicated_edges_count_once() {
+        let mut graph = WotGraph::new();
+        let p1 = graph.add_node(1).unwrap();
+        let p2 = graph.add_node(2).unwrap();
+        graph.add_edge(p1, p2, Relation::Follow);
+        graph.add_edge(p1, p2, Relation::Follow);
+
+        assert_eq!(graph.top_k(p1, 1, 10), vec![(p2, 1)]);
+    }
+
+    #[test]
+    fn matches_dump_wot() {
+        let mut graph = sample_graph(50);
+        for i in 0..50 {
+            graph.add_edge(
+                NodeIndex::new(i),
+                NodeIndex::new((i * 11 + 5) % 50),
+                Relation::Follow,
+            );
+        }
+
+        let source = node_idx(&graph, 0);
+        for max_hops in 0..4 {
+            let mut expected: Vec<_> = graph
+                .inner
+                .node_indices()
+                .filter(|idx| *idx != source)
+                .map(|idx| (idx, graph.dump_wot(source, idx, max_hops)))
+                .filter(|(_, score)| *score > 0)
+                .collect();
+            expected.sort_by_key(|(idx, score)| (-score, *idx));
+            expected.truncate(5);
+
+            assert_eq!(graph.top_k(source, max_hops, 5), expected);
+        }
+    }
+
+    #[test]
+    fn cancelled() {
+        let graph = sample_graph(50);
+        let source = NodeIndex::new(0);
+        let cancel = crate::progress::CancellationToken::new();
+        assert_eq!(
+            graph.top_k_cancellable(source, 3, 5, &cancel).unwrap(),
+            graph.top_k(source, 3, 5)
+        );
+
+        cancel.cancel();
+        assert!(matches!(
+            graph.top_k_cancellable(source, 3, 5, &cancel),
+            Err(crate::error::Error::Cancelled)
+        ));
+    }
+}
+
+mod source_view {
+    use super::*;
+
+    #[test]
+    fn matches_dump_wot() {
+        let graph = sample_graph(30);
+
+        let source = node_idx(&graph, 0);
+        for max_hops in 0..4 {
+            let mut view = graph.source_view(source, max_hops);
+            for target in graph.inner.node_indices() {
+                assert_eq!(
+                    view.dump_wot(&graph, target),
+                    graph.dump_wot(source, target, max_hops)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn refresh_on_new_follow_in_network() {
+        let mut graph = WotGraph::new();
+        let p1 = graph.add_node(1).unwrap();
+        let p2 = graph.add_node(2).unwrap();
+        let p3 = graph.add_node(3).unwrap();
+        let p4 = graph.add_node(4).unwrap();
+        graph.add_edge(p1, p2, Relation::Follow);
+        graph.add_edge(p3, p4, Relation::Follow);
+
+        let mut view = graph.source_view(p1, 2);
+        assert_eq!(view.dump_wot(&graph, p4), 0);
+        assert_eq!(view.hop(&graph, p3), None);
+
+        graph.add_unique_edge(p2, p3, Relation::Follow);
+        assert!(view.is_stale(&graph));
+        assert_eq!(view.dump_wot(&graph, p4), 1);
+        assert_eq!(view.hop(&graph, p3), Some(2));
+        assert_eq!(view.dump_wot(&graph, p3), 1);
+    }
+
+    #[test]
+    fn unrelated_edges_keep_view() {
+        let mut graph = WotGraph::new();
+        let p1 = graph.add_node(1).unwrap();
+        let p2 = graph.add_node(2).unwrap();
+        let p3 = graph.add_node(3).unwrap();
+        let p4 = graph.add_node(4).unwrap();
+        graph.add_edge(p1, p2, Relation::Follow);
+
+        let mut view = graph.source_view(p1, 1);
+
+        // Outside the network, from the last hop and a mute
+        graph.add_edge(p3, p4, Relation::Follow);
+        graph.add_edge(p2, p3, Relation::Follow);
+        graph.add_edge(p1, p4, Relation::Mute);
+        assert!(!view.is_stale(&graph));
+        assert_eq!(view.dump_wot(&graph, p3), 1);
+        assert_eq!(view.dump_wot(&graph, p4), -1);
+    }
+
+    #[test]
+    fn inner_mut_invalidates() {
+        let mut graph = WotGraph::new();
+        let p1 = graph.add_node(1).unwrap();
+        let p2 = graph.add_node(2).unwrap();
+        let edge = graph.add_edge(p1, p2, Relation::Follow).unwrap();
+
+        let mut view = graph.source_view(p1, 1);
+        assert_eq!(view.dump_wot(&graph, p2), 1);
+
+        graph.inner_mut().remove_edge(edge);
+        assert!(view.is_stale(&graph));
+        assert_eq!(view.dump_wot(&graph, p2), 0);
+    }
+
+    #[test]
+    fn other_graph_rebuilds() {
+        let mut graph = WotGraph::new();
+        let p1 = graph.add_node(1).unwrap();
+        let p2 = graph.add_node(2).unwrap();
+        graph.add_edge(p1, p2, Relation::Follow);
+
+        // Same revision and edge count, but the follow is reversed
+        let mut other = WotGraph::new();
+        other.add_node(1).unwrap();
+        other.add_node(2).unwrap();
+        other.add_edge(p2, p1, Relation::Follow);
+
+        let mut view = graph.source_view(p1, 1);
+        assert!(!view.is_stale(&graph));
+        assert!(view.is_stale(&other));
+        assert_eq!(view.dump_wot(&other, p2), 0);
+        assert_eq!(view.hop(&other, p2), None);
+    }
+}
+
+mod ms_bfs {
+    use super::*;
+    use crate::traits::basic::BasicOperationsExt;
+
+    #[test]
+    fn no_sources() {
+        let graph = sample_graph(200);
+        assert!(graph.multi_source_hops(&[], 3).is_empty());
+        assert!(
+            graph
+                .multi_source_dump_wot(&[], NodeIndex::new(1), 3)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn missing_source() {
+        let graph = sample_graph(200);
+        let hops = graph.multi_source_hops(&[NodeIndex::new(500), NodeIndex::new(1)], 1);
+        assert!(hops[0].is_empty());
+        assert_eq!(hops[1].get(&NodeIndex::new(1)), Some(&0));
+    }
+
+    #[test]
+    fn matches_single_source_hops() {
+        let graph = sample_graph(200);
+        // More than a batch, with a duplicated source
+        let mut sources: Vec<_> = graph.inner.node_indices().take(150).collect();
+        sources.push(sources[3]);
+
+        for max_hops in 0..4 {
+            let hops = graph.multi_source_hops(&sources, max_hops);
+            for (source, hops) in sources.iter().zip(hops) {
+                assert_eq!(hops, graph.inner.nodes_in_hops(*source, max_hops));
+            }
+        }
+    }
+
+    #[test]
+    fn matches_dump_wot() {
+        let graph = sample_graph(200);
+        let sources: Vec<_> = graph.inner.node_indices().collect();
+
+        for max_hops in 0..4 {
+            for target in [5, 42, 199] {
+                let target = node_idx(&graph, target);
+                let expected: Vec<_> = sources
+                    .iter()
+                    .map(|source| graph.dump_wot(*source, target, max_hops))
+                    .collect();
+                assert_eq!(
+                    graph.multi_source_dump_wot(&sources, target, max_hops),
+                    expected
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn cancelled_dump_wot() {
+        let graph = sample_graph(200);
+        let sources: Vec<_> = (0..200).map(NodeIndex::new).collect();
+        let target = NodeIndex::new(42);
+        let cancel = crate::progress::CancellationToken::new();
+        assert_eq!(
+            graph
+                .multi_source_dump_wot_cancellable(&sources, target, 3, &cancel)
+                .unwrap(),
+            graph.multi_source_dump_wot(&sources, target, 3)
+        );
+
+        cancel.cancel();
+        assert!(matches!(
+            graph.multi_source_dump_wot_cancellable(&sources, target, 3, &cancel),
+            Err(crate::error::Error::Cancelled)
+        ));
+    }
+
+    #[test]
+    fn cancelled_hops() {
+        let graph = sample_graph(200);
+        let sources: Vec<_> = (0..200).map(NodeIndex::new).collect();
+        let cancel = crate::progress::CancellationToken::new();
+        assert_eq!(
+            graph
+                .multi_source_hops_cancellable(&sources, 3, &cancel)
+                .unwrap(),
+            graph.multi_source_hops(&sources, 3)
+        );
+
+        cancel.cancel();
+        assert!(matches!(
+            graph.multi_source_hops_cancellable(&sources, 3, &cancel),
+            Err(crate::error::Error::Cancelled)
+        ));
+    }
+}
+
+mod frozen {
+    use super::*;
+
+    /// The sample graph with a duplicated follow of each node.
+    fn frozen_graph() -> WotGraph {
+        let mut graph = sample_graph(50);
+        for i in 0..50 {
+            graph.add_edge(
+                NodeIndex::new(i),
+                NodeIndex::new((i * 3 + 2) % 50),
+                Relation::Follow,
+            );
+        }
+        graph
+    }
+
+    #[test]
+    fn empty() {
+        let frozen = WotGraph::new().freeze();
+        assert_eq!(frozen.node_count(), 0);
+        assert_eq!(frozen.edge_count(), 0);
+        assert_eq!(
+            frozen
+                .neighbors(NodeIndex::new(0), Relation::Follow, Direction::Outgoing)
+                .count(),
+            0
+        );
+        assert_eq!(frozen.dump_wot(NodeIndex::new(0), NodeIndex::new(1), 2), 0);
+    }
+
+    #[test]
+    fn node_index() {
+        let mut graph = WotGraph::new();
+        let pkey = Keys::generate().public_key;
+        graph.add_node(1).unwrap();
+        let node = graph.add_node_pkey(&pkey).unwrap();
+
+        let frozen = graph.freeze();
+        assert_eq!(frozen.node_index(&pkey), Some(node));
+        assert_eq!(frozen.node_index(&Keys::generate().public_key), None);
+        assert_eq!(frozen.nodes(), &[1, graph.inner[node]]);
+    }
+
+    #[test]
+    fn unknown_relations_are_dropped() {
+        let mut graph = WotGraph::new();
+        let p1 = graph.add_node(1).unwrap();
+        let p2 = graph.add_node(2).unwrap();
+        graph.add_edge(p1, p2, Relation::Follow);
+        graph.inner_mut().add_edge(p1, p2, 7);
+
+        let frozen = graph.freeze();
+        assert_eq!(frozen.edge_count(), 1);
+    }
+
+    #[test]
+    fn matches_graph() {
+        let graph = frozen_graph();
+        let frozen = graph.freeze();
+        assert_eq!(frozen.node_count(), graph.inner.node_count());
+        assert_eq!(frozen.edge_count(), graph.inner.edge_count());
+
+        for node in graph.inner.node_indices() {
+            for relation in [Relation::Follow, Relation::Mute] {
+                for direction in [Direction::Outgoing, Direction::Incoming] {
+                    let mut expected: Vec<_> = graph.neighbors(node, relation, direction).collect();
+                    expected.sort();
+                    let neighbors: Vec<_> = frozen.neighbors(node, relation, direction).collect();
+                    assert_eq!(neighbors, expected);
+                }
+            }
+        }
+
+        let source = node_idx(&graph, 0);
+        for max_hops in 0..4 {
+            for target in graph.inner.node_indices() {
+                assert_eq!(
+                    frozen.count_neighbors_in_hops(source, target, Relation::Follow, max_hops),
+                    graph.count_neighbors_in_hops(source, target, Relation::Follow, max_hops)
+                );
+                assert_eq!(
+                    frozen.dump_wot(source, target, max_hops),
+                    graph.dump_wot(source, target, max_hops)
+                );
+            }
+        }
+    }
+}
+
+mod mapped {
+    use super::*;
+    use crate::{
+        error::{Error, GraphSerializationError},
+        mapped::MappedWotGraph,
+    };
+
+    #[test]
+    fn empty() {
+        let mut bytes = Vec::new();
+        WotGraph::new().freeze().export_mapped(&mut bytes).unwrap();
+
+        let mapped = MappedWotGraph::from_bytes(bytes).unwrap();
+        assert_eq!(mapped.node_count(), 0);
+        assert_eq!(mapped.edge_count(), 0);
+        assert_eq!(mapped.node_weight(NodeIndex::new(0)), None);
+        assert_eq!(mapped.dump_wot(NodeIndex::new(0), NodeIndex::new(1), 2), 0);
+    }
+
+    #[test]
+    fn matches_frozen() {
+        let graph = sample_graph(51);
+        let frozen = graph.freeze();
+        let mut bytes = Vec::new();
+        frozen.export_mapped(&mut bytes).unwrap();
+        assert_eq!(bytes.len() % 8, 0);
+
+        let mapped = MappedWotGraph::from_bytes(bytes.as_slice()).unwrap();
+        assert_eq!(mapped.node_count(), frozen.node_count());
+        assert_eq!(mapped.edge_count(), frozen.edge_count());
+
+        for node in graph.inner.node_indices() {
+            assert_eq!(mapped.node_weight(node), Some(graph.inner[node]));
+            for relation in [Relation::Follow, Relation::Mute] {
+                for direction in [Direction::Outgoing, Direction::Incoming] {
+                    assert!(
+                        mapped
+                            .neighbors(node, relation, direction)
+                            .eq(frozen.neighbors(node, relation, direction))
+                    );
+                }
+            }
+        }
+
+        let source = node_idx(&graph, 0);
+        for max_hops in 0..4 {
+            for target in graph.inner.node_indices() {
+                assert_eq!(
+                    mapped.dump_wot(source, target, max_hops),
+                    graph.dump_wot(source, target, max_hops)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn node_index() {
+        let mut graph = WotGraph::new();
+        let pkey = Keys::generate().public_key;
+        graph.add_node(1).unwrap();
+        let node = graph.add_node_pkey(&pkey).unwrap();
+
+        let mut bytes = Vec::new();
+        graph.freeze().export_mapped(&mut bytes).unwrap();
+        let mapped = MappedWotGraph::from_bytes(bytes).unwrap();
+        assert_eq!(mapped.node_index(&pkey), Some(node));
+        assert_eq!(mapped.node_index(&Keys::generate().public_key), None);
+    }
+
+    #[test]
+    fn invalid_magic() {
+        let mut bytes = Vec::new();
+        sample_graph(51).freeze().export_mapped(&mut bytes).unwrap();
+        bytes[0] = b'X';
+
+        assert!(matches!(
+            MappedWotGraph::from_bytes(bytes),
+            Err(Error::GraphSerializationError(
+                GraphSerializationError::NotAWotGraph
+            ))
+        ));
+    }
+
+    #[test]
+    fn unsupported_version() {
+        let mut bytes = Vec::new();
+        sample_graph(51).freeze().export_mapped(&mut bytes).unwrap();
+        bytes[8] = 42;
+
+        assert!(matches!(
+            MappedWotGraph::from_bytes(bytes),
+            Err(Error::GraphSerializationError(
+                GraphSerializationError::UnsupportedVersion(42)
+            ))
+        ));
+    }
+
+    #[test]
+    fn truncated() {
+        let mut bytes = Vec::new();
+        sample_graph(51).freeze().export_mapped(&mut bytes).unwrap();
+        bytes.truncate(bytes.len() - 8);
+
+        assert!(matches!(
+            MappedWotGraph::from_bytes(bytes),
+            Err(Error::GraphSerializationError(
+                GraphSerializationError::InsufficientData(_)
+            ))
+        ));
+        assert!(MappedWotGraph::from_bytes([0u8; 10]).is_err());
+    }
+
+    #[test]
+    fn huge_counts() {
+        let mut bytes = Vec::new();
+        sample_graph(51).freeze().export_mapped(&mut bytes).unwrap();
+        bytes[16..24].copy_from_slice(&u64::MAX.to_le_bytes());
+
+        assert!(MappedWotGraph::from_bytes(bytes).is_err());
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn open_file() {
+        let graph = sample_graph(51);
+        let path =
+            std::env::temp_dir().join(format!("nostr-wot-mapped-{}.bin", std::process::id()));
+        graph.freeze().export_to_file_mapped(&path).unwrap();
+
+        let mapped = MappedWotGraph::open(&path).unwrap();
+        let source = node_idx(&graph, 0);
+        let target = node_idx(&graph, 42);
+        assert_eq!(
+            mapped.dump_wot(source, target, 3),
+            graph.dump_wot(source, target, 3)
+        );
+
+        drop(mapped);
+        std::fs::remove_file(path).unwrap();
+    }
+}
+
+#[cfg(feature = "serde")]
+mod serialization {
+    use super::*;
+    use crate::options::{DuplicatePolicy, ImportOptions};
+
+    #[test]
+    fn roundtrip() {
+        let mut graph = WotGraph::new();
+        let p1 = graph.add_node(1).unwrap();
+        let p2 = graph.add_node(2).unwrap();
+        let p3 = graph.add_node(2).unwrap();
+        graph.add_edge(p1, p2, Relation::Follow);
+        graph.add_edge(p3, p1, Relation::Mute);
+
+        let json = serde_json::to_string(&graph).unwrap();
+        assert_eq!(
+            json,
+            r#"{"nodes":[1,2,2],"edges":[[0,"follow",1],[2,"mute",0]]}"#
+        );
+
+        let deserialized: WotGraph = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized.inner.node_count(), 3);
+        assert_eq!(
+            deserialized
+                .neighbors(p3, Relation::Mute, Direction::Outgoing)
+                .collect::<Vec<_>>(),
+            [p1]
+        );
+        assert_eq!(serde_json::to_string(&deserialized).unwrap(), json);
+    }
+
+    #[test]
+    fn relation() {
+        assert_eq!(serde_json::to_string(&Relation::Mute).unwrap(), r#""mute""#);
+        assert_eq!(
+            serde_json::from_str::<Relation>(r#""follow""#).unwrap(),
+            Relation::Follow
+        );
+        assert_eq!(
+            Relation::Mute.as_str(),
+            serde_json::to_value(Relation::Mute).unwrap()
+        );
+    }
+
+    #[test]
+    fn missing_node() {
+        let json = r#"{"nodes":[1],"edges":[[0,"follow",1]]}"#;
+        assert!(serde_json::from_str::<WotGraph>(json).is_err());
+    }
+
+    #[test]
+    fn invalid_relation() {
+        for json in [
+            r#"{"nodes":[1,2],"edges":[[0,7,1]]}"#,
+            r#"{"nodes":[1,2],"edges":[[0,"block",1]]}"#,
+        ] {
+            assert!(serde_json::from_str::<WotGraph>(json).is_err());
+        }
+
+        let mut graph = WotGraph::new();
+        let p1 = graph.add_node(1).unwrap();
+        graph.inner.add_edge(p1, p1, 7);
+        assert!(serde_json::to_string(&graph).is_err());
+    }
+
+    #[test]
+    fn deserialize_with_options() {
+        let json = r#"{"nodes":[1,2,2],"edges":[[0,"follow",1],[2,"mute",0]]}"#;
+        let deserialize = |options: &ImportOptions| {
+            let mut deserializer = serde_json::Deserializer::from_str(json);
+            WotGraph::deserialize_with(&mut deserializer, options)
+        };
+
+        // The duplicate is merged by default
+        let graph = deserialize(&ImportOptions::default()).unwrap();
+        assert_eq!(graph.inner.node_count(), 2);
+        assert!(
+            graph
+                .inner
+                .contains_edge(node_idx(&graph, 2), node_idx(&graph, 1))
+        );
+
+        for (options, message) in [
+            (ImportOptions::default().max_nodes(2), "Limit exceeded"),
+            (ImportOptions::default().max_edges(1), "Limit exceeded"),
+            (
+                ImportOptions::default().duplicates(DuplicatePolicy::Error),
+                "Duplicate node",
+            ),
+        ] {
+            let Err(err) = deserialize(&options) else {
+                panic!("expected an error");
+            };
+            assert!(err.to_string().contains(message), "{err}");
+        }
+    }
+}
+
+mod page_rank {
+    use super::*;
+
+    #[test]
+    fn empty() {
+        assert!(WotGraph::new().page_rank(0.85, 20).is_empty());
+    }
+
+    #[test]
+    fn follows_only() {
+        let mut graph = WotGraph::new();
+        let p1 = graph.add_node(1).unwrap();
+        let p2 = graph.add_node(2).unwrap();
+        let p3 = graph.add_node(3).unwrap();
+        graph.add_edge(p1, p3, Relation::Follow);
+        graph.add_edge(p2, p3, Relation::Follow);
+        graph.add_edge(p3, p1, Relation::Mute);
+
+        let ranks = graph.page_rank(0.85, 50);
+        assert!((ranks.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        assert!(ranks[p3.index()] > ranks[p1.index()]);
+        assert!((ranks[p1.index()] - ranks[p2.index()]).abs() < 1e-9);
+    }
+
+    #[test]
+    #[should_panic]
+    fn invalid_damping_factor() {
+        let mut graph = WotGraph::new();
+        graph.add_node(1).unwrap();
+        graph.page_rank(1.5, 1);
+    }
+
+    #[test]
+    fn cancelled() {
+        let graph = sample_graph(50);
+        let cancel = crate::progress::CancellationToken::new();
+        assert_eq!(
+            graph.page_rank_cancellable(0.85, 20, &cancel).unwrap(),
+            graph.page_rank(0.85, 20)
+        );
+
+        cancel.cancel();
+        assert!(matches!(
+            graph.page_rank_cancellable(0.85, 20, &cancel),
+            Err(crate::error::Error::Cancelled)
+        ));
+    }
+}
+
+mod formats {
+    use super::*;
+    use crate::{error::Error, options::AttributeOptions};
+
+    #[test]
+    fn graphml() {
+        let graph = triangle_graph(None);
+        let mut data = Vec::new();
+        graph
+            .export_graphml(&mut data, &AttributeOptions::default())
+            .unwrap();
+        let data = String::from_utf8(data).unwrap();
+
+        assert!(data.contains(r#"<data key="hash">0000000000000003</data>"#));
+        assert!(data.contains(r#"<edge id="e2" source="n0" target="n2">"#));
+        assert!(data.contains(r#"<data key="relation">mute</data>"#));
+        assert!(!data.contains("page_rank"));
+        assert!(!data.contains("dump_wot"));
+    }
+
+    #[test]
+    fn graphml_scores() {
+        let graph = triangle_graph(None);
+        let options = AttributeOptions::default()
+            .page_rank(0.85, 20)
+            .dump_wot(node_idx(&graph, 1), 2);
+        let mut data = Vec::new();
+        graph.export_graphml(&mut data, &options).unwrap();
+        let data = String::from_utf8(data).unwrap();
+
+        assert!(data.contains(r#"attr.name="page_rank" attr.type="double""#));
+        assert!(data.contains(r#"attr.name="dump_wot" attr.type="long""#));
+        let rank = graph.page_rank(0.85, 20)[2];
+        assert!(data.contains(&format!(r#"<data key="page_rank">{rank}</data>"#)));
+        assert!(data.contains(r#"<data key="dump_wot">0</data>"#));
+        assert!(data.contains(r#"<data key="dump_wot">1</data>"#));
+    }
+
+    #[test]
+    fn gexf() {
+        let graph = triangle_graph(None);
+        let options = AttributeOptions::default().dump_wot(node_idx(&graph, 1), 2);
+        let mut data = Vec::new();
+        graph.export_gexf(&mut data, &options).unwrap();
+        let data = String::from_utf8(data).unwrap();
+
+        assert!(data.contains(r#"<node id="1" label="0000000000000002">"#));
+        assert!(data.contains(r#"<attvalue for="dump_wot" value="1"/>"#));
+        assert!(data.contains(r#"<edge id="0" source="0" target="1" label="follow">"#));
+        assert!(data.contains(r#"<attvalue for="relation" value="mute"/>"#));
+        assert_eq!(data.matches("<node ").count(), 3);
+        assert_eq!(data.matches("<edge ").count(), 3);
+    }
+
+    #[test]
+    fn invalid_damping_factor() {
+        let graph = triangle_graph(None);
+        for damping_factor in [-0.1, 1.5, f64::NAN] {
+            let options = AttributeOptions::default().page_rank(damping_factor, 20);
+            assert!(matches!(
+                graph.export_graphml(Vec::new(), &options),
+                Err(Error::InvalidDampingFactor(_))
+            ));
+            assert!(matches!(
+                graph.export_gexf(Vec::new(), &options),
+                Err(Error::InvalidDampingFactor(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn dot() {
+        let mut graph = triangle_graph(None);
+        let p4 = graph.add_node(4).unwrap();
+        let p5 = graph.add_node(5).unwrap();
+        graph.add_edge(node_idx(&graph, 3), p4, Relation::Follow);
+        graph.add_edge(node_idx(&graph, 2), p5, Relation::Mute);
+
+        let mut data = Vec::new();
+        graph
+            .export_dot(&mut data, node_idx(&graph, 1), 1, Some(p5))
+            .unwrap();
+        let data = String::from_utf8(data).unwrap();
+
+        assert!(data.starts_with("digraph wot {"));
+        assert!(data.contains(
+            r#"n0 [label="0000000000000001\nhop 0", style=filled, fillcolor="lightblue"];"#
+        ));
+        assert!(data.contains(r#"n1 [label="0000000000000002\nhop 1"];"#));
+        assert!(data.contains(r#"n4 [label="0000000000000005", style=filled, fillcolor="gold"];"#));
+        assert!(data.contains(r#"n0 -> n1 [color="darkgreen", label="follow"];"#));
+        assert!(data.contains(r#"n1 -> n4 [color="red", style=dashed, label="mute"];"#));
+        // Outside the network
+        assert!(!data.contains("n2 "));
+        assert!(!data.contains("n3 "));
+    }
+
+    #[test]
+    fn dot_missing_source() {
+        let graph = triangle_graph(None);
+        let mut data = Vec::new();
+        graph
+            .export_dot(&mut data, NodeIndex::new(10), 2, None)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(data).unwrap(),
+            "digraph wot {\n  node [shape=box, fontname=\"monospace\"];\n}\n"
+        );
+    }
+
+    #[test]
+    fn invalid_relation() {
+        let mut graph = triangle_graph(None);
+        let (p1, p2) = (node_idx(&graph, 1), node_idx(&graph, 2));
+        graph.inner_mut().add_edge(p1, p2, 7);
+
+        assert!(matches!(
+            graph.export_gexf(Vec::new(), &AttributeOptions::default()),
+            Err(Error::GraphSerializationError(
+                crate::error::GraphSerializationError::InvalidRelation(7)
+            ))
+        ));
+    }
+}
+
+mod patch {
+    use super::*;
+    use crate::{
+        error::{Error, GraphSerializationError},
+        patch::GraphPatch,
+    };
+
+    /// Nodes 0..10 following the next node, and node 0 muting node 5.
+    fn chain_graph() -> WotGraph {
+        let mut graph = WotGraph::new();
+        let nodes: Vec<_> = (0..10).map(|i| graph.add_node(i).unwrap()).collect();
+        for pair in nodes.windows(2) {
+            graph.add_edge(pair[0], pair[1], Relation::Follow);
+        }
+        graph.add_edge(nodes[0], nodes[5], Relation::Mute);
+        graph
+    }
+
+    #[test]
+    fn identical() {
+        assert!(chain_graph().diff(&chain_graph()).is_empty());
+    }
+
+    #[test]
+    fn diff_and_apply() {
+        let mut old = chain_graph();
+        let mut new = chain_graph();
+        let p3 = node_idx(&new, 3);
+        new.inner_mut().remove_node(p3);
+        let p11 = new.add_node(11).unwrap();
+        new.add_edge(node_idx(&new, 0), p11, Relation::Follow);
+        new.add_edge(p11, node_idx(&new, 9), Relation::Mute);
+        let edge = new
+            .inner
+            .find_edge(node_idx(&new, 0), node_idx(&new, 5))
+            .unwrap();
+        new.inner_mut().remove_edge(edge);
+
+        let patch = old.diff(&new);
+        assert_eq!(patch.added_nodes, [11]);
+        assert_eq!(patch.removed_nodes, [3]);
+        assert_eq!(
+            patch.added_edges,
+            [(0, Relation::Follow, 11), (11, Relation::Mute, 9)]
+        );
+        assert_eq!(
+            patch.removed_edges,
+            [
+                (0, Relation::Mute, 5),
+                (2, Relation::Follow, 3),
+                (3, Relation::Follow, 4)
+            ]
+        );
+
+        old.apply_patch(&patch).unwrap();
+        assert!(old.diff(&new).is_empty());
+        assert_eq!(old.inner.node_count(), 10);
+        assert_eq!(old.inner.edge_count(), 9);
+        assert_eq!(old.dump_wot(node_idx(&old, 0), node_idx(&old, 9), 1), -1);
+
+        // Applying it again changes nothing
+        old.apply_patch(&patch).unwrap();
+        assert!(old.diff(&new).is_empty());
+    }
+
+    #[test]
+    fn missing_node() {
+        let mut graph = chain_graph();
+        let patch = GraphPatch {
+            removed_nodes: vec![1],
+            added_edges: vec![(0, Relation::Follow, 1)],
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            graph.apply_patch(&patch),
+            Err(Error::GraphSerializationError(
+                GraphSerializationError::NodeNotFound(1)
+            ))
+        ));
+        assert!(graph.diff(&chain_graph()).is_empty());
+    }
+
+    #[test]
+    fn export_import() {
+        let patch = GraphPatch {
+            added_nodes:   vec![11, 12],
+            removed_nodes: vec![3],
+            added_edges:   vec![
+                (0, Relation::Follow, 11),
+                (0, Relation::Mute, 12),
+                (11, Relation::Mute, 9),
+            ],
+            removed_edges: vec![(2, Relation::Follow, 3)],
+        };
+
+        let data = patch.export().unwrap();
+        // Header, nodes, two groups of added edges and one of removed edges
+        // (source, count and edges), and the checksum
+        assert_eq!(data.len(), 12 + 3 * 8 + (9 + 2 * 9) + (9 + 9) + (9 + 9) + 8);
+        assert_eq!(GraphPatch::import(&data).unwrap(), patch);
+        assert_eq!(
+            GraphPatch::import(&GraphPatch::default().export().unwrap()).unwrap(),
+            GraphPatch::default()
+        );
+    }
+
+    #[test]
+    fn import_invalid() {
+        let mut data = GraphPatch {
+            added_nodes: vec![1, 2],
+            added_edges: vec![(1, Relation::Follow, 2)],
+            ..Default::default()
+        }
+        .export()
+        .unwrap();
+
+        assert!(matches!(
+            GraphPatch::import(&data[..data.len() - 12]),
+            Err(Error::GraphSerializationError(
+                GraphSerializationError::InsufficientData(_)
+            ))
+        ));
+        assert!(matches!(
+            GraphPatch::import(&chain_graph().export().unwrap()),
+            Err(Error::GraphSerializationError(
+                GraphSerializationError::NotAWotGraph
+            ))
+        ));
+
+        let len = data.len();
+        data[len - 9] ^= 1;
+        assert!(matches!(
+            GraphPatch::import(&data),
+            Err(Error::GraphSerializationError(
+                GraphSerializationError::ChecksumMismatch(..)
+            ))
+        ));
+    }
+}
+
+mod merge {
+    use super::*;
+
+    fn edges_graph(edges: &[(u64, Relation, u64)]) -> WotGraph {
+        let mut graph = WotGraph::new();
+        for (source, relation, target) in edges {
+            let source = graph.add_unique_node(*source).unwrap();
+            let target = graph.add_unique_node(*target).unwrap();
+            graph.add_edge(source, target, *relation);
+        }
+        graph
+    }
+
+    fn outgoing(graph: &WotGraph, source: u64, relation: Relation) -> Vec<u64> {
+        let mut targets: Vec<_> = graph
+            .neighbors(node_idx(graph, source), relation, Direction::Outgoing)
+            .map(|idx| graph.inner[idx])
+            .collect();
+        targets.sort_unstable();
+        targets
+    }
+
+    #[test]
+    fn union() {
+        let mut graph = edges_graph(&[(1, Relation::Follow, 2), (1, Relation::Mute, 3)]);
+        let other = edges_graph(&[
+            (4, Relation::Follow, 1),
+            (1, Relation::Follow, 2),
+            (1, Relation::Follow, 4),
+        ]);
+
+        graph.merge(&other);
+        assert_eq!(graph.inner.node_count(), 4);
+        assert_eq!(graph.inner.edge_count(), 4);
+        assert_eq!(outgoing(&graph, 1, Relation::Follow), [2, 4]);
+        assert_eq!(outgoing(&graph, 1, Relation::Mute), [3]);
+        assert_eq!(outgoing(&graph, 4, Relation::Follow), [1]);
+
+        // Merging again adds nothing
+        graph.merge(&other);
+        assert_eq!(graph.inner.edge_count(), 4);
+    }
+
+    #[test]
+    fn newer_lists() {
+        let edges = [
+            (1, Relation::Follow, 2),
+            (1, Relation::Follow, 3),
+            (1, Relation::Mute, 4),
+            (2, Relation::Follow, 3),
+        ];
+        let other = edges_graph(&[
+            (1, Relation::Follow, 4),
+            (1, Relation::Mute, 5),
+            (2, Relation::Follow, 1),
+            (3, Relation::Follow, 1),
+        ]);
+        let timestamps = |author: u64, relation: Relation| {
+            match (author, relation) {
+                (1, Relation::Follow) => Some(10),
+                (1, Relation::Mute) => Some(30),
+                _ => None,
+            }
+        };
+        let other_timestamps = |author: u64, _| (author == 1).then_some(20);
+
+        let mut merged = edges_graph(&edges);
+        merged.merge_with(&other, timestamps, other_timestamps);
+        // The newer follow list replaces the graph one
+        assert_eq!(outgoing(&merged, 1, Relation::Follow), [4]);
+        // The graph mute list is newer
+        assert_eq!(outgoing(&merged, 1, Relation::Mute), [4]);
+        // Unknown timestamps, the lists are unioned
+        assert_eq!(outgoing(&merged, 2, Relation::Follow), [1, 3]);
+        assert_eq!(outgoing(&merged, 3, Relation::Follow), [1]);
+        assert_eq!(merged.inner.node_count(), 5);
+
+        // Without timestamps everything is unioned
+        let mut merged = edges_graph(&edges);
+        merged.merge_with(&other, |_, _| None, other_timestamps);
+        assert_eq!(outgoing(&merged, 1, Relation::Follow), [2, 3, 4]);
+        assert_eq!(outgoing(&merged, 1, Relation::Mute), [4, 5]);
+    }
+
+    #[test]
+    fn newer_empty_lists() {
+        let mut graph = edges_graph(&[
+            (1, Relation::Follow, 2),
+            (1, Relation::Follow, 3),
+            (2, Relation::Follow, 3),
+        ]);
+        let other = edges_graph(&[(3, Relation::Follow, 1), (2, Relation::Follow, 1)]);
+        let timestamps = |author: u64, relation: Relation| {
+            match (author, relation) {
+                (1, Relation::Follow) => Some(10),
+                (3, Relation::Follow) => Some(30),
+                _ => None,
+            }
+        };
+        let other_timestamps =
+            |author: u64, relation| (author != 2 && relation == Relation::Follow).then_some(20);
+
+        graph.merge_with(&other, timestamps, other_timestamps);
+        // The newer empty list of `other` replaces the graph one
+        assert!(outgoing(&graph, 1, Relation::Follow).is_empty());
+        // The newer empty list of the graph is kept
+        assert!(outgoing(&graph, 3, Relation::Follow).is_empty());
+        assert_eq!(outgoing(&graph, 2, Relation::Follow), [1, 3]);
+    }
+}
+
+#[cfg(feature = "arrow")]
+mod arrow {
+    use arrow_array::{Array, Float64Array, Int64Array, StringArray, UInt32Array, UInt64Array};
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    use super::*;
+    use crate::{error::Error, options::AttributeOptions};
+
+    #[test]
+    fn record_batches() {
+        let pkey = Keys::generate().public_key();
+        let graph = triangle_graph(Some(&pkey));
+        let options = AttributeOptions::default()
+            .page_rank(0.85, 20)
+            .dump_wot(NodeIndex::new(0), 2);
+        let nodes = graph.nodes_record_batch(&[pkey], &options).unwrap();
+
+        assert_eq!(nodes.num_rows(), 3);
+        let pubkeys = nodes.column_by_name("pubkey").unwrap();
+        let pubkeys = pubkeys.as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(pubkeys.value(0), pkey.to_hex());
+        assert!(pubkeys.is_null(1));
+        let hashes = nodes.column_by_name("hash").unwrap();
+        let hashes = hashes.as_any().downcast_ref::<UInt64Array>().unwrap();
+        assert_eq!(hashes.value(2), 3);
+        let ranks = nodes.column_by_name("page_rank").unwrap();
+        let ranks = ranks.as_any().downcast_ref::<Float64Array>().unwrap();
+        assert_eq!(ranks.values().to_vec(), graph.page_rank(0.85, 20));
+        let scores = nodes.column_by_name("dump_wot").unwrap();
+        let scores = scores.as_any().downcast_ref::<Int64Array>().unwrap();
+        assert_eq!(scores.value(1), 1);
+
+        let edges = graph.edges_record_batch().unwrap();
+        assert_eq!(edges.num_rows(), 3);
+        let targets = edges.column_by_name("target").unwrap();
+        let targets = targets.as_any().downcast_ref::<UInt32Array>().unwrap();
+        assert_eq!(targets.values().to_vec(), [1, 2, 2]);
+        let relations = edges.column_by_name("relation").unwrap();
+        let relations = relations.as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(relations.value(2), "mute");
+    }
+
+    #[test]
+    fn without_scores() {
+        let graph = triangle_graph(None);
+        let nodes = graph
+            .nodes_record_batch(&[], &AttributeOptions::default())
+            .unwrap();
+        assert_eq!(nodes.num_columns(), 3);
+        assert_eq!(nodes.column(2).null_count(), 3);
+    }
+
+    #[test]
+    fn invalid_damping_factor() {
+        let options = AttributeOptions::default().page_rank(1.5, 20);
+        assert!(matches!(
+            triangle_graph(None).nodes_record_batch(&[], &options),
+            Err(Error::InvalidDampingFactor(damping_factor)) if damping_factor == 1.5
+        ));
+    }
+
+    #[test]
+    fn parquet() {
+        let pkey = Keys::generate().public_key();
+        let graph = triangle_graph(Some(&pkey));
+        let path = |table: &str| {
+            std::env::temp_dir().join(format!("nostr-wot-{table}-{}.parquet", std::process::id()))
+        };
+        let (nodes_path, edges_path) = (path("nodes"), path("edges"));
+        graph
+            .export_parquet(
+                std::fs::File::create(&nodes_path).unwrap(),
+                std::fs::File::create(&edges_path).unwrap(),
+                &[pkey],
+                &AttributeOptions::default(),
+            )
+            .unwrap();
+
+        let read = |path: &std::path::Path| {
+            ParquetRecordBatchReaderBuilder::try_new(std::fs::File::open(path).unwrap())
+                .unwrap()
+                .build()
+                .unwrap()
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap()
+        };
+        let nodes = read(&nodes_path);
+        assert_eq!(
+            nodes[0],
+            graph
+                .nodes_record_batch(&[pkey], &AttributeOptions::default())
+                .unwrap()
+        );
+        let edges = read(&edges_path);
+        assert_eq!(edges[0], graph.edges_record_batch().unwrap());
+
+        std::fs::remove_file(nodes_path).unwrap();
+        std::fs::remove_file(edges_path).unwrap();
+    }
+}
+
+#[cfg(feature = "csv")]
+mod csv {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::{
+        error::{Error, GraphSerializationError, Limit},
+        options::ImportOptions,
+        progress::{CancellationToken, Progress},
+    };
+
+    #[test]
+    fn roundtrip() {
+        let pkey = Keys::generate().public_key();
+        let mut graph = WotGraph::new();
+        let p1 = graph.add_node_pkey(&pkey).unwrap();
+        let p2 = graph.add_node(2).unwrap();
+        graph.add_edge(p1, p2, Relation::Follow);
+        graph.add_edge(p2, p1, Relation::Mute);
+
+        let mut data = Vec::new();
+        graph.export_csv(&mut data, &[pkey]).unwrap();
+        let text = String::from_utf8(data.clone()).unwrap();
+        assert_eq!(
+            text,
+            format!(
+                "follower,followee,relation\n{0},0000000000000002,follow\n0000000000000002,{0},\
+                 mute\n",
+                pkey.to_hex()
+            )
+        );
+
+        let imported = WotGraph::import_csv(data.as_slice()).unwrap();
+        assert_eq!(imported.node_index(&pkey), Some(p1));
+        assert_eq!(imported.dump_wot(p2, p1, 1), -1);
+    }
+
+    #[test]
+    fn npub_without_header() {
+        let follower = Keys::generate().public_key();
+        let followee = Keys::generate().public_key();
+        let data = format!(
+            "{}, {}, follow\n{},{},mute\n",
+            nostr::nips::nip19::ToBech32::to_bech32(&follower).unwrap(),
+            followee.to_hex(),
+            followee.to_hex(),
+            follower.to_hex()
+        );
+
+        let graph = WotGraph::import_csv(data.as_bytes()).unwrap();
+        assert_eq!(graph.inner.node_count(), 2);
+        assert_eq!(graph.inner.edge_count(), 2);
+        let (p1, p2) = (
+            graph.node_index(&follower).unwrap(),
+            graph.node_index(&followee).unwrap(),
+        );
+        assert_eq!(
+            graph
+                .neighbors(p1, Relation::Follow, Direction::Outgoing)
+                .collect::<Vec<_>>(),
+            [p2]
+        );
+    }
+
+    #[test]
+    fn invalid_records() {
+        let data = "follower,followee,relation\n0000000000000001,0000000000000002,block\n";
+        assert!(matches!(
+            WotGraph::import_csv(data.as_bytes()),
+            Err(Error::GraphSerializationError(GraphSerializationError::UnknownRelation(name))) if name == "block"
+        ));
+
+        let data = "0000000000000001,bob,follow\n";
+        assert!(matches!(
+            WotGraph::import_csv(data.as_bytes()),
+            Err(Error::GraphSerializationError(GraphSerializationError::InvalidNodeKey(key))) if key == "bob"
+        ));
+
+        let data = "0000000000000001,0000000000000002,follow\n0000000000000001\n";
+        assert!(matches!(
+            WotGraph::import_csv(data.as_bytes()),
+            Err(Error::Csv(_))
+        ));
+    }
+
+    #[test]
+    fn import_options() {
+        let data = "0000000000000001,0000000000000002,follow\n0000000000000002,0000000000000003,\
+                    follow\n0000000000000003,0000000000000001,mute\n";
+        assert!(matches!(
+            WotGraph::import_csv_with(data.as_bytes(), &ImportOptions::default().max_nodes(2)),
+            Err(Error::GraphSerializationError(
+                GraphSerializationError::LimitExceeded(Limit::Nodes, 3, 2)
+            ))
+        ));
+        assert!(matches!(
+            WotGraph::import_csv_with(data.as_bytes(), &ImportOptions::default().max_edges(2)),
+            Err(Error::GraphSerializationError(
+                GraphSerializationError::LimitExceeded(Limit::Edges, 3, 2)
+            ))
+        ));
+
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+        assert!(matches!(
+            WotGraph::import_csv_with(data.as_bytes(), &ImportOptions::default().cancel(cancel)),
+            Err(Error::Cancelled)
+        ));
+
+        // The totals are unknown until the end of the list
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let reports_clone = Arc::clone(&reports);
+        let options = ImportOptions::default()
+            .progress(move |progress| reports_clone.lock().unwrap().push(progress));
+        WotGraph::import_csv_with(data.as_bytes(), &options).unwrap();
+        assert_eq!(
+            *reports.lock().unwrap(),
+            [
+                Progress::default(),
+                Progress {
+                    nodes:       3,
+                    total_nodes: 3,
+                    edges:       3,
+                    total_edges: 3,
+                }
+            ]
+        );
+    }
+}
+
+#[cfg(feature = "json")]
+mod json {
+    use super::*;
+    use crate::{
+        error::{Error, GraphSerializationError, Limit},
+        options::{DuplicatePolicy, ImportOptions},
+        progress::CancellationToken,
+    };
+
+    #[test]
+    fn roundtrip() {
+        let pkey = Keys::generate().public_key();
+        let mut graph = WotGraph::new();
+        let p1 = graph.add_node_pkey(&pkey).unwrap();
+        let p2 = graph.add_node(0xab).unwrap();
+        graph.add_edge(p1, p2, Relation::Follow);
+        graph.add_edge(p2, p1, Relation::Mute);
+
+        let json = graph.export_json(&[pkey]).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["nodes"][0], pkey.to_hex());
+        assert_eq!(value["nodes"][1], "00000000000000ab");
+        assert_eq!(value["edges"][1]["from"], "00000000000000ab");
+        assert_eq!(value["edges"][1]["relation"], "mute");
+
+        let imported = WotGraph::import_json(&json).unwrap();
+        assert_eq!(imported.node_index(&pkey), Some(p1));
+        assert_eq!(imported.dump_wot(p2, p1, 1), -1);
+        assert_eq!(imported.export_json(&[pkey]).unwrap(), json);
+    }
+
+    #[test]
+    fn npub() {
+        let pkey = Keys::generate().public_key();
+        let npub = nostr::nips::nip19::ToBech32::to_bech32(&pkey).unwrap();
+        let json = format!(
+            r#"{{"nodes":["{npub}","{}"],"edges":[{{"from":"0000000000000001","to":"{npub}","relation":"follow"}}]}}"#,
+            "0000000000000001"
+        );
+
+        let graph = WotGraph::import_json(&json).unwrap();
+        let node = graph.node_index(&pkey).unwrap();
+        assert_eq!(
+            graph
+                .neighbors(node_idx(&graph, 1), Relation::Follow, Direction::Outgoing)
+                .collect::<Vec<_>>(),
+            [node]
+        );
+    }
+
+    #[test]
+    fn unknown_relation() {
+        let json = r#"{"nodes":["0000000000000001"],"edges":[{"from":"0000000000000001","to":"0000000000000001","relation":"block"}]}"#;
+        assert!(matches!(
+            WotGraph::import_json(json),
+            Err(Error::GraphSerializationError(GraphSerializationError::UnknownRelation(name))) if name == "block"
+        ));
+    }
+
+    #[test]
+    fn invalid_key() {
+        let json = r#"{"nodes":["alice"],"edges":[]}"#;
+        assert!(matches!(
+            WotGraph::import_json(json),
+            Err(Error::GraphSerializationError(GraphSerializationError::InvalidNodeKey(key))) if key == "alice"
+        ));
+
+        let json = r#"{"nodes":["0000000000000001"],"edges":[{"from":"0000000000000001","to":"0000000000000002","relation":"follow"}]}"#;
+        assert!(matches!(
+            WotGraph::import_json(json),
+            Err(Error::GraphSerializationError(
+                GraphSerializationError::NodeNotFound(2)
+            ))
+        ));
+    }
+
+    #[test]
+    fn import_options() {
+        let json = r#"{"nodes":["0000000000000001","0000000000000002","0000000000000001"],"edges":[{"from":"0000000000000002","to":"0000000000000001","relation":"follow"}]}"#;
+        let graph = WotGraph::import_json_with(json, &ImportOptions::default()).unwrap();
+        assert_eq!(graph.inner.node_count(), 2);
+
+        assert!(matches!(
+            WotGraph::import_json_with(json, &ImportOptions::default().max_nodes(2)),
+            Err(Error::GraphSerializationError(
+                GraphSerializationError::LimitExceeded(Limit::Nodes, 3, 2)
+            ))
+        ));
+        assert!(matches!(
+            WotGraph::import_json_with(json, &ImportOptions::default().max_edges(0)),
+            Err(Error::GraphSerializationError(
+                GraphSerializationError::LimitExceeded(Limit::Edges, 1, 0)
+            ))
+        ));
+        assert!(matches!(
+            WotGraph::import_json_with(
+                json,
+                &ImportOptions::default().duplicates(DuplicatePolicy::Error)
+            ),
+            Err(Error::GraphSerializationError(
+                GraphSerializationError::DuplicateNode(1)
+            ))
+        ));
+
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+        assert!(matches!(
+            WotGraph::import_json_with(json, &ImportOptions::default().cancel(cancel)),
+            Err(Error::Cancelled)
+        ));
+    }
+}
+
+/// Nodes `0..n`, each following the nodes `i * 7 + 1` and `i * 3 + 2` and
+/// muting the node `i * 13 + 3` (mod `n`).
+fn sample_graph(n: u64) -> WotGraph {
+    let mut graph = WotGraph::with_capacity(n as usize, n as usize * 3);
+    for i in 0..n {
+        graph.add_node(i).unwrap();
+    }
+    for i in 0..n as usize {
+        let node = NodeIndex::new(i);
+        let n = n as usize;
+        for j in [i * 7 + 1, i * 3 + 2] {
+            graph.add_edge(node, NodeIndex::new(j % n), Relation::Follow);
+        }
+        graph.add_edge(node, NodeIndex::new((i * 13 + 3) % n), Relation::Mute);
+    }
+    graph
+}
+
+/// Nodes `1`, `2` and `3`, the first following the second, which follows the
+/// third, and muting the third. The first node is `pkey` if given.
+fn triangle_graph(pkey: Option<&nostr::key::PublicKey>) -> WotGraph {
+    let mut graph = WotGraph::new();
+    let p1 = match pkey {
+        Some(pkey) => graph.add_node_pkey(pkey).unwrap(),
+        None => graph.add_node(1).unwrap(),
+    };
+    let p2 = graph.add_node(2).unwrap();
+    let p3 = graph.add_node(3).unwrap();
+    graph.add_edge(p1, p2, Relation::Follow);
+    graph.add_edge(p2, p3, Relation::Follow);
+    graph.add_edge(p1, p3, Relation::Mute);
+    graph
+}
+
 fn node_idx(graph: &WotGraph, number: u64) -> NodeIndex {
     let inner = &graph.inner;
     inner.node_indices().find(|i| inner[*i] == number).unwrap()
@@ -22,17 +22,17 @@ pub impl crate::GraphType {
     /// of nodes muting the target within the same distance.
     #[inline]
     fn dump_wot(&self, source: NodeIndex, target: NodeIndex, max_hops: u8) -> isize {
-        isize::try_from(self.count_matches_in_hops(source, target, Relation::Follow, max_hops))
-            .unwrap_or(isize::MAX)
-            .checked_sub(
-                isize::try_from(self.count_matches_in_hops(
-                    source,
-                    target,
-                    Relation::Mute,
-                    max_hops,
-                ))
-                .unwrap_or(isize::MAX),
-            )
-            .unwrap_or(isize::MIN)
+        score(
+            self.count_matches_in_hops(source, target, Relation::Follow, max_hops),
+            self.count_matches_in_hops(source, target, Relation::Mute, max_hops),
+        )
     }
 }
+
+/// The dump WoT score, `follows - mutes` saturated to [`isize`] bounds.
+pub(crate) fn score(follows: usize, mutes: usize) -> isize {
+    isize::try_from(follows)
+        .unwrap_or(isize::MAX)
+        .checked_sub(isize::try_from(mutes).unwrap_or(isize::MAX))
+        .unwrap_or(isize::MIN)
+}
@@ -0,0 +1,151 @@
+// Copyright (c) 2026, Awiteb <a@4rs.nl>
+//     lightweight nostr Web of Trust library
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use petgraph::{Direction, graph::NodeIndex};
+use rayon::prelude::*;
+
+use crate::{
+    progress::CancellationToken,
+    relations::Relation,
+    traits::basic::BasicOperationsExt,
+    utils,
+};
+
+/// The lowest supported HyperLogLog precision (16 registers per node).
+pub const MIN_PRECISION: u8 = 4;
+/// The highest supported HyperLogLog precision (65536 registers per node).
+pub const MAX_PRECISION: u8 = 16;
+
+#[easy_ext::ext(HyperAnfExt)]
+pub impl crate::GraphType {
+    /// Estimates, for every node, how many nodes are within `max_hops` from
+    /// it, following the `Follow` edges. The node itself is counted (hop 0).
+    ///
+    /// Each node holds a HyperLogLog counter with `2^precision` registers,
+    /// and every hop is a single parallel pass that unions the counters of
+    /// the followed nodes (HyperANF). The precision is clamped between
+    /// [`MIN_PRECISION`] and [`MAX_PRECISION`], the relative standard error
+    /// is about `1.04 / sqrt(2^precision)`.
+    ///
+    /// The returned vector is indexed by [`NodeIndex::index`].
+    ///
+    /// # Time Complexity
+    /// O(max_hops * (V + E) * 2^precision)
+    ///
+    /// # Space Complexity
+    /// O(V * 2^precision), two counters sets are kept during a pass
+    fn approx_neighborhood_sizes(&self, max_hops: u8, precision: u8) -> Vec<f64> {
+        self.approx_neighborhood_sizes_cancellable(max_hops, precision, &CancellationToken::new())
+            .expect("a new token is never cancelled")
+    }
+
+    /// Same as [`HyperAnfExt::approx_neighborhood_sizes`], but returns
+    /// [`Error::Cancelled`] once the token is cancelled. The token is checked
+    /// for every node of every pass.
+    ///
+    /// [`Error::Cancelled`]: crate::error::Error::Cancelled
+    fn approx_neighborhood_sizes_cancellable(
+        &self,
+        max_hops: u8,
+        precision: u8,
+        cancel: &CancellationToken,
+    ) -> Result<Vec<f64>, crate::error::Error> {
+        let precision = precision.clamp(MIN_PRECISION, MAX_PRECISION);
+        let registers = 1usize << precision;
+
+        // Hop 0: every counter contains only its own node
+        let mut counters = vec![0u8; self.node_count() * registers];
+        counters
+            .par_chunks_mut(registers)
+            .enumerate()
+            .for_each(|(idx, counter)| {
+                let (register, rank) = hll_register(idx, precision);
+                counter[register] = rank;
+            });
+
+        for _ in 0..max_hops {
+            cancel.check()?;
+            let mut next_counters = counters.clone();
+            let changed = next_counters
+                .par_chunks_mut(registers)
+                .enumerate()
+                .map(|(idx, counter)| {
+                    let mut changed = false;
+                    if cancel.is_cancelled() {
+                        return changed;
+                    }
+                    for neighbor in self.get_matches_neighbors(
+                        NodeIndex::new(idx),
+                        Relation::Follow,
+                        Direction::Outgoing,
+                    ) {
+                        let start = neighbor.index() * registers;
+                        let neighbor_counter = &counters[start..start + registers];
+                        for (register, neighbor_register) in
+                            counter.iter_mut().zip(neighbor_counter)
+                        {
+                            if *neighbor_register > *register {
+                                *register = *neighbor_register;
+                                changed = true;
+                            }
+                        }
+                    }
+                    changed
+                })
+                .reduce(|| false, |a, b| a || b);
+
+            counters = next_counters;
+
+            // The counters are stable, the next hops will not reach new nodes
+            if !changed {
+                break;
+            }
+        }
+
+        cancel.check()?;
+        Ok(counters.par_chunks(registers).map(hll_estimate).collect())
+    }
+}
+
+/// Returns the register index and its rank for the given node index.
+fn hll_register(idx: usize, precision: u8) -> (usize, u8) {
+    let hash = utils::hash_bytes(&(idx as u64).to_le_bytes());
+    let register = (hash >> (64 - precision)) as usize;
+    // The remaining bits, with a sentinel bit to bound the rank
+    let remaining = (hash << precision) | (1 << (precision - 1));
+    (register, remaining.leading_zeros() as u8 + 1)
+}
+
+/// Estimates the cardinality of a HyperLogLog counter.
+fn hll_estimate(counter: &[u8]) -> f64 {
+    let registers = counter.len() as f64;
+    let alpha = match counter.len() {
+        16 => 0.673,
+        32 => 0.697,
+        64 => 0.709,
+        _ => 0.7213 / (1.0 + 1.079 / registers),
+    };
+
+    let (sum, zeros) = counter.iter().fold((0.0, 0usize), |(sum, zeros), rank| {
+        (
+            sum + 2f64.powi(-i32::from(*rank)),
+            zeros + usize::from(*rank == 0),
+        )
+    });
+    let estimate = alpha * registers * registers / sum;
+
+    // Small range correction (linear counting)
+    if estimate <= 2.5 * registers && zeros != 0 {
+        registers * (registers / zeros as f64).ln()
+    } else {
+        estimate
+    }
+}
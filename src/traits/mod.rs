@@ -14,3 +14,15 @@ pub mod basic;
 
 /// Dump WoT implmentation.
 pub mod dump_wot;
+
+/// HyperANF neighborhood function approximation.
+pub mod hyper_anf;
+
+/// Top-k trusted targets.
+pub mod top_k;
+
+/// Multi-source bit-parallel BFS.
+pub mod ms_bfs;
+
+/// PageRank over the follow edges.
+pub mod page_rank;
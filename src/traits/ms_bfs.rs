@@ -0,0 +1,271 @@
+// Copyright (c) 2026, Awiteb <a@4rs.nl>
+//     lightweight nostr Web of Trust library
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use std::collections::{HashMap, HashSet};
+
+use petgraph::{Direction, graph::NodeIndex};
+
+use crate::{progress::CancellationToken, relations::Relation, traits::basic::BasicOperationsExt};
+
+/// Number of sources traversed together, one bit of a `u64` each.
+pub const BATCH_SIZE: usize = 64;
+
+#[easy_ext::ext(MultiSourceBfsExt)]
+pub impl crate::GraphType {
+    /// Traverses the `Follow` edges from many sources at once, up to
+    /// `max_hops`, and calls `visit` with the source position in `sources`,
+    /// the reached node and its hop distance. Each node is visited once per
+    /// source, starting by the source itself at hop 0.
+    ///
+    /// The sources are processed in batches of [`BATCH_SIZE`], each batch is
+    /// a single traversal where every node holds a bitmask of the sources
+    /// that reached it (MS-BFS), so the shared parts of the sources networks
+    /// are only expanded once. Sources that don't exist in the graph are
+    /// skipped.
+    ///
+    /// # Time Complexity
+    /// O(ceil(S / 64) * (V + E)) where S is the sources, V is the reachable
+    /// vertices and E is their edges
+    ///
+    /// # Space Complexity
+    /// O(N) for the bitmasks, where N is the graph nodes
+    fn multi_source_bfs<F>(&self, sources: &[NodeIndex], max_hops: u8, visit: F)
+    where
+        F: FnMut(usize, NodeIndex, u8),
+    {
+        self.multi_source_bfs_cancellable(sources, max_hops, visit, &CancellationToken::new())
+            .expect("a new token is never cancelled")
+    }
+
+    /// Same as [`MultiSourceBfsExt::multi_source_bfs`], but returns
+    /// [`Error::Cancelled`] once the token is cancelled. The token is checked
+    /// before every batch.
+    ///
+    /// [`Error::Cancelled`]: crate::error::Error::Cancelled
+    fn multi_source_bfs_cancellable<F>(
+        &self,
+        sources: &[NodeIndex],
+        max_hops: u8,
+        mut visit: F,
+        cancel: &CancellationToken,
+    ) -> Result<(), crate::error::Error>
+    where
+        F: FnMut(usize, NodeIndex, u8),
+    {
+        let mut state = MsBfsState::new(self.node_count());
+        for (batch_idx, batch) in sources.chunks(BATCH_SIZE).enumerate() {
+            cancel.check()?;
+            let offset = batch_idx * BATCH_SIZE;
+            state.run(self, batch, max_hops, |bit, node, hop| {
+                visit(offset + bit, node, hop)
+            });
+        }
+        Ok(())
+    }
+
+    /// Collects, for each source, the nodes within `max_hops` following the
+    /// `Follow` edges with their hop distance. Same as calling
+    /// [`BasicOperationsExt::nodes_in_hops`] for each source, using
+    /// [`MultiSourceBfsExt::multi_source_bfs`].
+    fn multi_source_hops(
+        &self,
+        sources: &[NodeIndex],
+        max_hops: u8,
+    ) -> Vec<HashMap<NodeIndex, u8>> {
+        self.multi_source_hops_cancellable(sources, max_hops, &CancellationToken::new())
+            .expect("a new token is never cancelled")
+    }
+
+    /// Same as [`MultiSourceBfsExt::multi_source_hops`], but returns
+    /// [`Error::Cancelled`] once the token is cancelled. The token is checked
+    /// before every batch.
+    ///
+    /// [`Error::Cancelled`]: crate::error::Error::Cancelled
+    fn multi_source_hops_cancellable(
+        &self,
+        sources: &[NodeIndex],
+        max_hops: u8,
+        cancel: &CancellationToken,
+    ) -> Result<Vec<HashMap<NodeIndex, u8>>, crate::error::Error> {
+        let mut hops = vec![HashMap::new(); sources.len()];
+        self.multi_source_bfs_cancellable(
+            sources,
+            max_hops,
+            |source, node, hop| {
+                hops[source].insert(node, hop);
+            },
+            cancel,
+        )?;
+        Ok(hops)
+    }
+
+    /// Counts the trust score between each source and the target within
+    /// `max_hops`. Same as calling [`dump_wot`] for each source, using a
+    /// multi-source traversal.
+    ///
+    /// [`dump_wot`]: crate::traits::dump_wot::DumpWotExt::dump_wot
+    fn multi_source_dump_wot(
+        &self,
+        sources: &[NodeIndex],
+        target: NodeIndex,
+        max_hops: u8,
+    ) -> Vec<isize> {
+        self.multi_source_dump_wot_cancellable(sources, target, max_hops, &CancellationToken::new())
+            .expect("a new token is never cancelled")
+    }
+
+    /// Same as [`MultiSourceBfsExt::multi_source_dump_wot`], but returns
+    /// [`Error::Cancelled`] once the token is cancelled. The token is checked
+    /// before every batch.
+    ///
+    /// [`Error::Cancelled`]: crate::error::Error::Cancelled
+    fn multi_source_dump_wot_cancellable(
+        &self,
+        sources: &[NodeIndex],
+        target: NodeIndex,
+        max_hops: u8,
+        cancel: &CancellationToken,
+    ) -> Result<Vec<isize>, crate::error::Error> {
+        let mut scores = vec![0isize; sources.len()];
+        if self.raw_nodes().get(target.index()).is_none() {
+            return Ok(scores);
+        }
+
+        let followers: HashSet<NodeIndex> = self
+            .get_matches_neighbors(target, Relation::Follow, Direction::Incoming)
+            .collect();
+        let muters: HashSet<NodeIndex> = self
+            .get_matches_neighbors(target, Relation::Mute, Direction::Incoming)
+            .collect();
+        if followers.is_empty() && muters.is_empty() {
+            return Ok(scores);
+        }
+
+        let mut state = MsBfsState::new(self.node_count());
+        for (batch_idx, batch) in sources.chunks(BATCH_SIZE).enumerate() {
+            cancel.check()?;
+            state.run(self, batch, max_hops, |_, _, _| {});
+
+            let batch_scores = &mut scores[batch_idx * BATCH_SIZE..];
+            for (nodes, delta) in [(&followers, 1), (&muters, -1)] {
+                for node in nodes {
+                    let mut seen = state.seen[node.index()];
+                    while seen != 0 {
+                        batch_scores[seen.trailing_zeros() as usize] += delta;
+                        seen &= seen - 1;
+                    }
+                }
+            }
+        }
+
+        Ok(scores)
+    }
+}
+
+/// The bitmasks of a multi-source traversal, reused between the batches.
+struct MsBfsState {
+    /// Sources that reached each node.
+    seen:          Vec<u64>,
+    /// Sources that reached each node in the current hop.
+    visit:         Vec<u64>,
+    /// Sources that reached each node in the next hop.
+    visit_next:    Vec<u64>,
+    /// Nodes with a non empty `visit` mask.
+    current_level: Vec<NodeIndex>,
+    /// Nodes with a non empty `visit_next` mask.
+    next_level:    Vec<NodeIndex>,
+    /// Nodes with a non empty `seen` mask, to reset them after the batch.
+    touched:       Vec<NodeIndex>,
+}
+
+impl MsBfsState {
+    /// Creates the state of a graph with `nodes` nodes.
+    fn new(nodes: usize) -> Self {
+        Self {
+            seen:          vec![0; nodes],
+            visit:         vec![0; nodes],
+            visit_next:    vec![0; nodes],
+            current_level: Vec::new(),
+            next_level:    Vec::new(),
+            touched:       Vec::new(),
+        }
+    }
+
+    /// Traverses the graph from a batch of at most 64 sources. The `seen`
+    /// masks are kept until the next run.
+    fn run<F>(&mut self, graph: &crate::GraphType, batch: &[NodeIndex], max_hops: u8, mut visit: F)
+    where
+        F: FnMut(usize, NodeIndex, u8),
+    {
+        debug_assert!(batch.len() <= BATCH_SIZE);
+
+        for node in self.touched.drain(..) {
+            self.seen[node.index()] = 0;
+        }
+        for node in self.current_level.drain(..) {
+            self.visit[node.index()] = 0;
+        }
+
+        // Hop 0: the sources themselves
+        for (bit, source) in batch.iter().enumerate() {
+            if source.index() >= self.seen.len() {
+                continue;
+            }
+
+            let mask = 1u64 << bit;
+            if self.seen[source.index()] == 0 {
+                self.touched.push(*source);
+                self.current_level.push(*source);
+            }
+            self.seen[source.index()] |= mask;
+            self.visit[source.index()] |= mask;
+            visit(bit, *source, 0);
+        }
+
+        for hop in 1..=max_hops {
+            for node in &self.current_level {
+                let node_visit = self.visit[node.index()];
+                for neighbor in
+                    graph.get_matches_neighbors(*node, Relation::Follow, Direction::Outgoing)
+                {
+                    let new = node_visit & !self.seen[neighbor.index()];
+                    if new != 0 {
+                        if self.visit_next[neighbor.index()] == 0 {
+                            self.next_level.push(neighbor);
+                        }
+                        self.visit_next[neighbor.index()] |= new;
+                    }
+                }
+            }
+
+            for node in self.current_level.drain(..) {
+                self.visit[node.index()] = 0;
+            }
+            for node in &self.next_level {
+                let mut new = self.visit_next[node.index()];
+                if self.seen[node.index()] == 0 {
+                    self.touched.push(*node);
+                }
+                self.seen[node.index()] |= new;
+                while new != 0 {
+                    visit(new.trailing_zeros() as usize, *node, hop);
+                    new &= new - 1;
+                }
+            }
+
+            std::mem::swap(&mut self.visit, &mut self.visit_next);
+            std::mem::swap(&mut self.current_level, &mut self.next_level);
+            if self.current_level.is_empty() {
+                break;
+            }
+        }
+    }
+}
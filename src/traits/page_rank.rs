@@ -0,0 +1,86 @@
+// Copyright (c) 2026, Awiteb <a@4rs.nl>
+//     lightweight nostr Web of Trust library
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use crate::{progress::CancellationToken, relations::Relation};
+
+#[easy_ext::ext(PageRankExt)]
+pub impl crate::GraphType {
+    /// Computes the PageRank of every node over the `Follow` edges, indexed
+    /// by node index. The ranks sum to 1.
+    ///
+    /// The rank of the nodes without `Follow` edges is spread over all the
+    /// nodes. Unlike [`petgraph::algo::page_rank()`], each iteration only walks
+    /// the edges once.
+    ///
+    /// # Time Complexity
+    /// O(iterations * (V + E))
+    ///
+    /// # Panics
+    /// If `damping_factor` is not between 0 and 1
+    fn page_rank(&self, damping_factor: f64, iterations: usize) -> Vec<f64> {
+        self.page_rank_cancellable(damping_factor, iterations, &CancellationToken::new())
+            .expect("a new token is never cancelled")
+    }
+
+    /// Same as [`PageRankExt::page_rank`], but returns [`Error::Cancelled`]
+    /// once the token is cancelled. The token is checked before every
+    /// iteration.
+    ///
+    /// # Panics
+    /// If `damping_factor` is not between 0 and 1
+    ///
+    /// [`Error::Cancelled`]: crate::error::Error::Cancelled
+    fn page_rank_cancellable(
+        &self,
+        damping_factor: f64,
+        iterations: usize,
+        cancel: &CancellationToken,
+    ) -> Result<Vec<f64>, crate::error::Error> {
+        assert!(
+            (0.0..=1.0).contains(&damping_factor),
+            "Damping factor should be between 0 and 1"
+        );
+        let node_count = self.node_count();
+        if node_count == 0 {
+            return Ok(Vec::new());
+        }
+
+        let follows: Vec<_> = self
+            .raw_edges()
+            .iter()
+            .filter(|edge| edge.weight == Relation::Follow as u8)
+            .map(|edge| (edge.source().index(), edge.target().index()))
+            .collect();
+        let mut out_degrees = vec![0usize; node_count];
+        for (source, _) in &follows {
+            out_degrees[*source] += 1;
+        }
+
+        let nodes = node_count as f64;
+        let mut ranks = vec![1.0 / nodes; node_count];
+        for _ in 0..iterations {
+            cancel.check()?;
+            let dangling: f64 = ranks
+                .iter()
+                .zip(&out_degrees)
+                .filter(|(_, degree)| **degree == 0)
+                .map(|(rank, _)| rank)
+                .sum();
+            let mut next =
+                vec![(1.0 - damping_factor + damping_factor * dangling) / nodes; node_count];
+            for (source, target) in &follows {
+                next[*target] += damping_factor * ranks[*source] / out_degrees[*source] as f64;
+            }
+            ranks = next;
+        }
+        Ok(ranks)
+    }
+}
@@ -0,0 +1,104 @@
+// Copyright (c) 2026, Awiteb <a@4rs.nl>
+//     lightweight nostr Web of Trust library
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet},
+};
+
+use petgraph::{Direction, graph::NodeIndex, visit::EdgeRef};
+
+use crate::{progress::CancellationToken, relations::Relation, traits::basic::BasicOperationsExt};
+
+#[easy_ext::ext(TopKExt)]
+pub impl crate::GraphType {
+    /// Returns the `k` highest scoring targets in the source's network,
+    /// sorted by score in descending order. Ties are ordered by node index.
+    ///
+    /// The score of each target is its [`dump_wot`] score from the source
+    /// within `max_hops`, only targets with a positive score are returned and
+    /// the source itself is excluded.
+    ///
+    /// The scores are accumulated from the outgoing edges of the source's
+    /// network in a single traversal, and the best `k` are kept in a
+    /// bounded heap.
+    ///
+    /// # Time Complexity
+    /// O(V + E + T * log(k)) where V is the nodes within `max_hops`, E is
+    /// their edges and T is the scored targets
+    ///
+    /// # Space Complexity
+    /// O(V + T) for the visited set and the scores
+    ///
+    /// [`dump_wot`]: crate::traits::dump_wot::DumpWotExt::dump_wot
+    fn top_k(&self, source: NodeIndex, max_hops: u8, k: usize) -> Vec<(NodeIndex, isize)> {
+        self.top_k_cancellable(source, max_hops, k, &CancellationToken::new())
+            .expect("a new token is never cancelled")
+    }
+
+    /// Same as [`TopKExt::top_k`], but returns [`Error::Cancelled`] once the
+    /// token is cancelled. The token is checked for every node of the
+    /// source's network.
+    ///
+    /// [`Error::Cancelled`]: crate::error::Error::Cancelled
+    fn top_k_cancellable(
+        &self,
+        source: NodeIndex,
+        max_hops: u8,
+        k: usize,
+        cancel: &CancellationToken,
+    ) -> Result<Vec<(NodeIndex, isize)>, crate::error::Error> {
+        if k == 0 {
+            return Ok(Vec::new());
+        }
+
+        let mut scores: HashMap<NodeIndex, isize> = HashMap::new();
+        // The relations of the current node, a node counts once per target
+        // and relation even with duplicated edges
+        let mut relations = HashSet::new();
+        for node in self.nodes_in_hops(source, max_hops).into_keys() {
+            cancel.check()?;
+            relations.clear();
+            for edge in self.edges_directed(node, Direction::Outgoing) {
+                if !relations.insert((edge.target(), *edge.weight())) {
+                    continue;
+                }
+
+                let score = scores.entry(edge.target()).or_default();
+                if *edge.weight() == Relation::Follow as u8 {
+                    *score += 1;
+                } else if *edge.weight() == Relation::Mute as u8 {
+                    *score -= 1;
+                }
+            }
+        }
+
+        // Min-heap of the best `k` targets, the lowest score (and the highest
+        // index on ties) on top to be evicted first
+        let mut heap = BinaryHeap::with_capacity(k.min(scores.len()).saturating_add(1));
+        for (target, score) in scores {
+            if target == source || score <= 0 {
+                continue;
+            }
+
+            heap.push(Reverse((score, Reverse(target))));
+            if heap.len() > k {
+                heap.pop();
+            }
+        }
+
+        Ok(heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse((score, Reverse(target)))| (target, score))
+            .collect())
+    }
+}
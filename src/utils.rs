@@ -9,8 +9,77 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
 // THE SOFTWARE.
 
+use std::collections::HashMap;
+
+#[cfg(any(feature = "arrow", feature = "csv", feature = "json"))]
+use nostr::key::PublicKey;
+use petgraph::{graph::NodeIndex, visit::EdgeRef};
+
+#[cfg(any(feature = "csv", feature = "json"))]
+use crate::error::GraphSerializationError;
+
+/// The seed of the xxHash 64-bit hashes.
+pub(crate) const HASH_SEED: u64 = 0xC0FFEE;
+
 /// Hashes the given byte slice using the xxHash 64-bit algorithm with a
 /// constant seed value.
 pub fn hash_bytes(bytes: &[u8]) -> u64 {
-    xxhash_rust::xxh64::xxh64(bytes, 0xC0FFEE)
+    xxhash_rust::xxh64::xxh64(bytes, HASH_SEED)
+}
+
+/// Maps the hashes of the public keys to them.
+#[cfg(any(feature = "arrow", feature = "csv", feature = "json"))]
+pub(crate) fn known_keys(known: &[PublicKey]) -> HashMap<u64, &PublicKey> {
+    known
+        .iter()
+        .map(|pkey| (hash_bytes(pkey.as_bytes()), pkey))
+        .collect()
+}
+
+/// The key of a node hash, its hex public key if it's known, otherwise the
+/// hash as 16 hex characters.
+#[cfg(any(feature = "csv", feature = "json"))]
+pub(crate) fn node_key(known: &HashMap<u64, &PublicKey>, hash: u64) -> String {
+    match known.get(&hash) {
+        Some(pkey) => pkey.to_hex(),
+        None => format!("{hash:016x}"),
+    }
+}
+
+/// Parses a node key, a hex or npub public key, or a 16 hex hash, to a node
+/// hash.
+#[cfg(any(feature = "csv", feature = "json"))]
+pub(crate) fn parse_node_key(key: &str) -> Result<u64, GraphSerializationError> {
+    if key.len() == 16 {
+        return u64::from_str_radix(key, 16)
+            .map_err(|_| GraphSerializationError::InvalidNodeKey(key.to_owned()));
+    }
+    PublicKey::parse(key)
+        .map(|pkey| hash_bytes(pkey.as_bytes()))
+        .map_err(|_| GraphSerializationError::InvalidNodeKey(key.to_owned()))
+}
+
+/// Maps the node hashes to their first index.
+pub(crate) fn node_map(graph: &crate::GraphType) -> HashMap<u64, NodeIndex> {
+    let mut map = HashMap::with_capacity(graph.node_count());
+    for idx in graph.node_indices() {
+        map.entry(graph[idx]).or_insert(idx);
+    }
+    map
+}
+
+/// Removes the edges matching `remove`, called with the source hash, the
+/// relation and the target hash of each edge.
+pub(crate) fn remove_edges<F: Fn(u64, u8, u64) -> bool>(graph: &mut crate::GraphType, remove: F) {
+    // Removing an edge moves the last edge to its index, so they are removed
+    // from the last index
+    let mut edges: Vec<_> = graph
+        .edge_references()
+        .filter(|edge| remove(graph[edge.source()], *edge.weight(), graph[edge.target()]))
+        .map(|edge| edge.id())
+        .collect();
+    edges.sort_unstable_by(|a, b| b.cmp(a));
+    for edge in edges {
+        graph.remove_edge(edge);
+    }
 }